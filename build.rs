@@ -8,7 +8,18 @@ fn main() {
     copy_to_output("tests/res/track_len.gpx", &env::var(KEY).unwrap()).expect(MSG);
     copy_to_output("tests/res/track_points.gpx", &env::var(KEY).unwrap()).expect(MSG);
     copy_to_output("tests/res/track_loc.gpx", &env::var(KEY).unwrap()).expect(MSG);
+    copy_to_output("tests/res/track_time.gpx", &env::var(KEY).unwrap()).expect(MSG);
+    copy_to_output("tests/res/track_gap.gpx", &env::var(KEY).unwrap()).expect(MSG);
+    copy_to_output("tests/res/track_ele.gpx", &env::var(KEY).unwrap()).expect(MSG);
+    copy_to_output("tests/res/track_dwell.gpx", &env::var(KEY).unwrap()).expect(MSG);
+    copy_to_output("tests/res/track_loops.gpx", &env::var(KEY).unwrap()).expect(MSG);
+    copy_to_output("tests/res/track_mode.gpx", &env::var(KEY).unwrap()).expect(MSG);
+    copy_to_output("tests/res/gates.gpx", &env::var(KEY).unwrap()).expect(MSG);
+    copy_to_output("tests/res/areas.gpx", &env::var(KEY).unwrap()).expect(MSG);
     copy_to_output("tests/res/route_len.gpx", &env::var(KEY).unwrap()).expect(MSG);
     copy_to_output("tests/res/route_points.gpx", &env::var(KEY).unwrap()).expect(MSG);
     copy_to_output("tests/res/pois.gpx", &env::var(KEY).unwrap()).expect(MSG);
+    copy_to_output("tests/res/malformed.gpx", &env::var(KEY).unwrap()).expect(MSG);
+    copy_to_output("tests/res/pois_route.gpx", &env::var(KEY).unwrap()).expect(MSG);
+    copy_to_output("tests/res/devices.ini", &env::var(KEY).unwrap()).expect(MSG);
 }
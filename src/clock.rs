@@ -0,0 +1,261 @@
+use gpx::Waypoint;
+use std::fmt;
+use std::str::FromStr;
+use time::{Date, Duration, OffsetDateTime, UtcOffset};
+use time_tz::{timezones, OffsetDateTimeExt, TimeZone, Tz};
+
+/// A time zone to determine the local calendar day of a timestamp.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Zone {
+    /// a fixed offset to UTC
+    Fixed(UtcOffset),
+    /// a time zone of the IANA database, which also considers the daylight saving time
+    Named(&'static Tz),
+}
+
+impl Zone {
+    /// Returns the local calendar date of the timestamp in this time zone.
+    ///
+    /// ```
+    /// use gpx_split::clock::*;
+    /// use time::macros::{date, datetime};
+    ///
+    /// let zone: Zone = "America/New_York".parse().unwrap();
+    /// assert_eq!(date!(2024-01-05), zone.date(datetime!(2024-01-06 03:00 UTC)));
+    /// let zone: Zone = "+05:30".parse().unwrap();
+    /// assert_eq!(date!(2024-01-06), zone.date(datetime!(2024-01-05 20:00 UTC)));
+    /// ```
+    #[must_use]
+    pub fn date(&self, time: OffsetDateTime) -> Date {
+        match self {
+            Zone::Fixed(offset) => time.to_offset(*offset).date(),
+            Zone::Named(tz) => time.to_timezone(*tz).date(),
+        }
+    }
+}
+
+impl FromStr for Zone {
+    type Err = String;
+
+    /// Parses either a fixed offset like +02:00, -5 or an IANA name like Europe/Berlin.
+    fn from_str(zone: &str) -> Result<Self, Self::Err> {
+        if zone.starts_with(['+', '-']) {
+            return parse_offset(zone)
+                .map(Zone::Fixed)
+                .ok_or(format!("invalid offset to UTC: {zone}"));
+        }
+        timezones::get_by_name(zone)
+            .map(Zone::Named)
+            .ok_or(format!("unknown time zone: {zone}"))
+    }
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Zone::Fixed(offset) => write!(f, "{offset}"),
+            Zone::Named(tz) => write!(f, "{}", tz.name()),
+        }
+    }
+}
+
+// parses an offset with hours and optional minutes, e.g. +2, -05:30
+fn parse_offset(offset: &str) -> Option<UtcOffset> {
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let (hours, minutes) = offset[1..].split_once(':').unwrap_or((&offset[1..], "0"));
+    let hours: i8 = hours.parse().ok()?;
+    let minutes: i8 = minutes.parse().ok()?;
+    UtcOffset::from_hms(sign * hours, sign * minutes, 0).ok()
+}
+
+/// Returns the timestamp of the waypoint, if it has one.
+///
+#[must_use]
+pub fn timestamp(point: &Waypoint) -> Option<OffsetDateTime> {
+    point.time.map(OffsetDateTime::from)
+}
+
+/// Calculates the time elapsed between the first and the last waypoint, which have a timestamp.
+/// Waypoints without a timestamp are skipped. If there are less than two waypoints with a timestamp,
+/// the elapsed time is zero.
+///
+/// ```
+/// use gpx::Waypoint;
+/// use geo::Point;
+/// use gpx_split::clock::*;
+/// use time::macros::datetime;
+///
+/// let mut p1 = Waypoint::new(Point::new(0.0, 0.0));
+/// p1.time = Some(datetime!(2024-01-06 16:00 UTC).into());
+/// let mut p2 = Waypoint::new(Point::new(0.0, 0.0));
+/// p2.time = Some(datetime!(2024-01-06 18:30 UTC).into());
+///
+/// assert_eq!(time::Duration::minutes(150), elapsed(&[p1, Waypoint::default(), p2]));
+/// ```
+#[must_use]
+pub fn elapsed(points: &[Waypoint]) -> Duration {
+    let first = points.iter().find_map(timestamp);
+    let last = points.iter().rev().find_map(timestamp);
+    match (first, last) {
+        (Some(first), Some(last)) => last - first,
+        _ => Duration::ZERO,
+    }
+}
+
+/// Calculates the sunset at the location of the waypoint on the date, which is the local solar date there.
+/// The calculation follows the sunrise equation with the refraction of the atmosphere, which is accurate
+/// to about a minute. Returns None, if the sun does not set or rise on that date, e.g. near the poles.
+///
+/// ```
+/// use gpx::Waypoint;
+/// use geo::Point;
+/// use gpx_split::clock::*;
+/// use time::macros::{date, datetime};
+///
+/// //Berlin on the winter solstice
+/// let berlin = Waypoint::new(Point::new(13.40, 52.52));
+/// let sunset = sunset(&berlin, date!(2024-12-21)).unwrap();
+/// assert!((sunset - datetime!(2024-12-21 14:54 UTC)).abs() < time::Duration::minutes(2));
+/// ```
+#[must_use]
+pub fn sunset(point: &Waypoint, date: Date) -> Option<OffsetDateTime> {
+    const J2000: f64 = 2_451_545.0;
+    const UNIX_EPOCH: f64 = 2_440_587.5;
+    let (lon, lat) = point.point().x_y();
+    //the Julian day at noon of the date, then the mean solar time at the longitude
+    let noon = f64::from(date.to_julian_day());
+    let mean = noon - J2000 + 0.0008 - lon / 360.0;
+    let anomaly = (357.5291 + 0.985_600_28 * mean)
+        .rem_euclid(360.0)
+        .to_radians();
+    let center =
+        1.9148 * anomaly.sin() + 0.02 * (2.0 * anomaly).sin() + 0.0003 * (3.0 * anomaly).sin();
+    let ecliptic = (anomaly.to_degrees() + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit = J2000 + mean + 0.0053 * anomaly.sin() - 0.0069 * (2.0 * ecliptic).sin();
+    let declination = (ecliptic.sin() * 23.4397_f64.to_radians().sin()).asin();
+    let lat = lat.to_radians();
+    let hour_angle = ((-0.833_f64).to_radians().sin() - lat.sin() * declination.sin())
+        / (lat.cos() * declination.cos());
+    if !(-1.0..=1.0).contains(&hour_angle) {
+        return None;
+    }
+    let set = transit + hour_angle.acos().to_degrees() / 360.0;
+    #[allow(clippy::cast_possible_truncation)]
+    let seconds = ((set - UNIX_EPOCH) * 86_400.0).round() as i64;
+    OffsetDateTime::from_unix_timestamp(seconds).ok()
+}
+
+/// Returns the local solar date of the time at the longitude in degrees, which is used to calculate the sunset.
+///
+#[must_use]
+pub fn solar_date(time: OffsetDateTime, lon: f64) -> Date {
+    #[allow(clippy::cast_possible_truncation)]
+    let offset = Duration::seconds((lon / 15.0 * 3600.0) as i64);
+    (time.to_offset(UtcOffset::UTC) + offset).date()
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+    use gpx::Waypoint;
+    use time::macros::{date, datetime, offset};
+
+    use super::*;
+
+    fn waypoint(time: Option<OffsetDateTime>) -> Waypoint {
+        let mut point = Waypoint::new(Point::new(0.0, 0.0));
+        point.time = time.map(Into::into);
+        point
+    }
+
+    #[test]
+    fn elapsed_without_time() {
+        assert_eq!(Duration::ZERO, elapsed(&[]));
+        assert_eq!(Duration::ZERO, elapsed(&[waypoint(None), waypoint(None)]));
+        let single = waypoint(Some(datetime!(2021-07-15 11:00 UTC)));
+        assert_eq!(Duration::ZERO, elapsed(&[single, waypoint(None)]));
+    }
+
+    #[test]
+    fn elapsed_skips_missing_time() {
+        let points = [
+            waypoint(None),
+            waypoint(Some(datetime!(2021-07-15 11:00:16 UTC))),
+            waypoint(None),
+            waypoint(Some(datetime!(2021-07-15 11:31:43 UTC))),
+            waypoint(None),
+        ];
+        let expected = Duration::minutes(31) + Duration::seconds(27);
+        assert_eq!(expected, elapsed(&points));
+    }
+
+    #[test]
+    fn sunset_at_locations() {
+        let at = |lon, lat| Waypoint::new(Point::new(lon, lat));
+        let near = |expected: OffsetDateTime, actual: Option<OffsetDateTime>| {
+            (expected - actual.unwrap()).abs() < Duration::minutes(2)
+        };
+        //New York in summer, Sydney in its summer and Quito near the equator
+        assert!(near(
+            datetime!(2024-06-21 00:31 UTC),
+            sunset(&at(-74.006, 40.713), date!(2024 - 06 - 20))
+        ));
+        assert!(near(
+            datetime!(2024-12-21 09:05 UTC),
+            sunset(&at(151.209, -33.868), date!(2024 - 12 - 21))
+        ));
+        assert!(near(
+            datetime!(2024-03-20 23:25 UTC),
+            sunset(&at(-78.467, -0.180), date!(2024 - 03 - 20))
+        ));
+        //midnight sun and polar night in Tromsø
+        assert_eq!(None, sunset(&at(18.955, 69.649), date!(2024 - 06 - 21)));
+        assert_eq!(None, sunset(&at(18.955, 69.649), date!(2024 - 12 - 21)));
+    }
+
+    #[test]
+    fn solar_date_at_longitude() {
+        let time = datetime!(2024-06-20 23:00 UTC);
+        assert_eq!(date!(2024 - 06 - 20), solar_date(time, -74.0));
+        assert_eq!(date!(2024 - 06 - 21), solar_date(time, 151.0));
+    }
+
+    #[test]
+    fn parse_zone() {
+        assert_eq!(Ok(Zone::Fixed(offset!(+2))), "+2".parse());
+        assert_eq!(Ok(Zone::Fixed(offset!(-05:30))), "-05:30".parse());
+        assert_eq!(
+            "Europe/Berlin",
+            "Europe/Berlin".parse::<Zone>().unwrap().to_string()
+        );
+        assert!("+26".parse::<Zone>().is_err());
+        assert!("+a".parse::<Zone>().is_err());
+        assert!("Europe/Nowhere".parse::<Zone>().is_err());
+    }
+
+    #[test]
+    fn date_in_zone() {
+        let time = datetime!(2021-07-15 22:30 UTC);
+        assert_eq!(
+            date!(2021 - 07 - 15),
+            "+00:00".parse::<Zone>().unwrap().date(time)
+        );
+        assert_eq!(
+            date!(2021 - 07 - 16),
+            "+02:00".parse::<Zone>().unwrap().date(time)
+        );
+        //summer time in Berlin
+        assert_eq!(
+            date!(2021 - 07 - 16),
+            "Europe/Berlin".parse::<Zone>().unwrap().date(time)
+        );
+        //winter time in Berlin
+        let time = datetime!(2021-01-15 22:30 UTC);
+        assert_eq!(
+            date!(2021 - 01 - 15),
+            "Europe/Berlin".parse::<Zone>().unwrap().date(time)
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use log::debug;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+/// A named preset for a device, which expands to the object to split,
+/// the limits and the output options of the command line.
+/// The values are kept as text, they are checked like the arguments of the command line.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Preset {
+    /// name of the preset like edge-530
+    pub name: String,
+    /// objects to split, route or track
+    pub trace: Option<String>,
+    /// methods to split with their maximum like ("point", "10000")
+    pub limits: Vec<(String, String)>,
+    /// split only when all maximum values are exceeded
+    pub all: bool,
+    /// split exactly at the maximum length
+    pub strict: bool,
+    /// directory for the new files, e.g. the folder of a mounted device
+    pub output: Option<String>,
+    /// number of tracks or routes the device can hold
+    pub files: Option<usize>,
+}
+
+impl Preset {
+    /// Returns the presets, which are built into the program.
+    #[must_use]
+    pub fn builtin() -> Vec<Preset> {
+        vec![
+            //a course on the Edge 530 has at most 10000 points,
+            //its file is kept below 1 MB, which is reached earlier by points with long names or extensions
+            Preset {
+                name: "edge-530".to_string(),
+                trace: Some("track".to_string()),
+                limits: vec![
+                    ("point".to_string(), "10000".to_string()),
+                    ("size".to_string(), "1000000".to_string()),
+                ],
+                files: Some(200),
+                ..Default::default()
+            },
+            //an eTrex stores 200 routes with 250 points each
+            Preset {
+                name: "etrex".to_string(),
+                trace: Some("route".to_string()),
+                limits: vec![("point".to_string(), "250".to_string())],
+                files: Some(200),
+                ..Default::default()
+            },
+        ]
+    }
+
+    /// Finds the preset with the given name. A preset of the user in the file
+    /// replaces a built-in preset with the same name.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error, if the file can't be read or the name is unknown.
+    pub fn find(name: &str, path: Option<&str>) -> Result<Preset> {
+        let mut presets = match path {
+            Some(path) => read_presets(path)?,
+            None => vec![],
+        };
+        presets.extend(Preset::builtin());
+        let names: Vec<String> = presets.iter().map(|preset| preset.name.clone()).collect();
+        presets
+            .into_iter()
+            .find(|preset| preset.name == name)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!(
+                        "unknown device {name}, expected one of {}",
+                        names.join(", ")
+                    ),
+                )
+            })
+    }
+}
+
+/// Reads the presets of the user from a file like this one, where a limit
+/// is the method to split and its maximum, which can be repeated:
+///
+/// ```ini
+/// # comment
+/// [fenix]
+/// trace = track
+/// limit = point:10000
+/// limit = size:1000000
+/// output = /media/GARMIN/Garmin/NewFiles
+/// files = 100
+/// ```
+///
+/// # Errors
+///
+/// Will return an error, if the file can't be read or a line is invalid.
+pub fn read_presets<P: AsRef<Path>>(path: P) -> Result<Vec<Preset>> {
+    let path = path.as_ref();
+    let content = fs::read_to_string(path).map_err(|err| {
+        Error::new(
+            err.kind(),
+            format!("can't read file with devices {}: {err}", path.display()),
+        )
+    })?;
+    let presets = parse_presets(&content).map_err(|(number, msg)| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid line {number} in {}: {msg}", path.display()),
+        )
+    })?;
+    debug!("read {} presets from {}", presets.len(), path.display());
+    Ok(presets)
+}
+
+fn parse_presets(content: &str) -> std::result::Result<Vec<Preset>, (usize, String)> {
+    let mut presets: Vec<Preset> = vec![];
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            presets.push(Preset {
+                name: name.trim().to_string(),
+                ..Default::default()
+            });
+            continue;
+        }
+        let fail = |msg: String| (index + 1, msg);
+        let Some(preset) = presets.last_mut() else {
+            return Err(fail("a value before the name of a device".to_string()));
+        };
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(fail(format!(
+                "expected a key and value like trace = route: {line}"
+            )));
+        };
+        let value = value.trim().to_string();
+        match key.trim() {
+            "trace" => preset.trace = Some(value),
+            "limit" => match value.split_once(':') {
+                Some((by, max)) => preset
+                    .limits
+                    .push((by.trim().to_string(), max.trim().to_string())),
+                None => return Err(fail(format!("expected a limit like point:10000: {value}"))),
+            },
+            "all" => preset.all = parse_flag(&value).map_err(fail)?,
+            "strict" => preset.strict = parse_flag(&value).map_err(fail)?,
+            "output" => preset.output = Some(value),
+            "files" => {
+                preset.files = Some(
+                    value
+                        .parse()
+                        .map_err(|err| fail(format!("invalid number of files {value}: {err}")))?,
+                );
+            }
+            key => return Err(fail(format!("unknown key {key}"))),
+        }
+    }
+    Ok(presets)
+}
+
+fn parse_flag(value: &str) -> std::result::Result<bool, String> {
+    value
+        .parse()
+        .map_err(|_| format!("expected true or false: {value}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_preset() {
+        let content = "
+            # my devices
+            [fenix]
+            trace = track
+            limit = point:10000
+            limit = mode:walk:7,bike:30
+            strict = true
+            output = /media/GARMIN
+            files = 100
+
+            [empty]
+        ";
+        let presets = parse_presets(content).unwrap();
+        assert_eq!(2, presets.len());
+        let fenix = &presets[0];
+        assert_eq!("fenix", fenix.name);
+        assert_eq!(Some("track".to_string()), fenix.trace);
+        assert_eq!(
+            vec![
+                ("point".to_string(), "10000".to_string()),
+                ("mode".to_string(), "walk:7,bike:30".to_string())
+            ],
+            fenix.limits
+        );
+        assert!(fenix.strict);
+        assert!(!fenix.all);
+        assert_eq!(Some("/media/GARMIN".to_string()), fenix.output);
+        assert_eq!(Some(100), fenix.files);
+        assert_eq!("empty", presets[1].name);
+        assert!(presets[1].limits.is_empty());
+    }
+
+    #[test]
+    fn parse_invalid_preset() {
+        assert_eq!(1, parse_presets("trace = route").unwrap_err().0);
+        assert_eq!(3, parse_presets("[a]\n\nlimit = 250").unwrap_err().0);
+        assert_eq!(2, parse_presets("[a]\nfoo = bar").unwrap_err().0);
+        assert_eq!(2, parse_presets("[a]\nall = yes").unwrap_err().0);
+        assert_eq!(2, parse_presets("[a]\nfiles = -1").unwrap_err().0);
+    }
+
+    #[test]
+    fn find_preset() {
+        let etrex = Preset::find("etrex", None).unwrap();
+        assert_eq!(Some("route".to_string()), etrex.trace);
+        assert_eq!(vec![("point".to_string(), "250".to_string())], etrex.limits);
+
+        //the built-in preset is split by the first of both limits
+        let edge = Preset::find("edge-530", None).unwrap();
+        assert_eq!(
+            vec![
+                ("point".to_string(), "10000".to_string()),
+                ("size".to_string(), "1000000".to_string())
+            ],
+            edge.limits
+        );
+        assert!(!edge.all);
+
+        let edge = Preset::find("edge-530", Some("target/debug/devices.ini")).unwrap();
+        assert_eq!(vec![("point".to_string(), "5000".to_string())], edge.limits);
+        assert_eq!(Some(200), edge.files);
+        let bike = Preset::find("bike", Some("target/debug/devices.ini")).unwrap();
+        assert!(bike.all);
+
+        let err = Preset::find("foo", None).unwrap_err();
+        assert_eq!(ErrorKind::NotFound, err.kind());
+        let err = Preset::find("etrex", Some("target/debug/missing.ini")).unwrap_err();
+        assert_eq!(ErrorKind::NotFound, err.kind());
+    }
+}
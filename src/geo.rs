@@ -1,11 +1,14 @@
+use geo::line_intersection::{line_intersection, LineIntersection};
 use geo::point;
 use geo::prelude::*;
-use geo::Point;
+use geo::{Line, LineString, Point, Polygon};
 use geo_types::Point as Geopoint;
 use geo_types::{coord, Rect};
 use geographiclib_rs::{DirectGeodesic, Geodesic, InverseGeodesic};
 use gpx::{Gpx, Waypoint};
 
+use crate::clock::{elapsed, timestamp};
+
 /// Calculates the distance between the 2 waypoints.
 /// Returns result in Meter.
 ///
@@ -27,6 +30,125 @@ pub fn distance_all(points: &[Waypoint]) -> f64 {
         .sum()
 }
 
+/// Calculates the distance from the first waypoint to each waypoint along the collection.
+/// Returns the results in Meter, where the first one is always zero.
+///
+pub fn distances_along(points: &[Waypoint]) -> Vec<f64> {
+    let points = collect_points(points);
+    let mut sum = 0.0;
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            if i > 0 {
+                sum += points[i - 1].geodesic_distance(p);
+            }
+            sum
+        })
+        .collect()
+}
+
+/// Calculates the effort from the first waypoint to each waypoint along the collection,
+/// which is the distance plus 10 times the ascent, i.e. 100 m of ascent count like 1 km.
+/// Returns the results in Meter, where the first one is always zero.
+///
+/// ```
+/// use gpx::Waypoint;
+/// use geo::Point;
+/// use gpx_split::loc::*;
+///
+/// let points: Vec<Waypoint> = [0.0, 100.0, 50.0]
+///     .iter()
+///     .map(|ele| {
+///         let mut p = Waypoint::new(Point::new(0.0, 0.0));
+///         p.elevation = Some(*ele);
+///         p
+///     })
+///     .collect();
+/// assert_eq!(vec![0.0, 1000.0, 1000.0], efforts_along(&points));
+/// ```
+#[must_use]
+pub fn efforts_along(points: &[Waypoint]) -> Vec<f64> {
+    let distances = distances_along(points);
+    let mut ascent = 0.0;
+    distances
+        .iter()
+        .enumerate()
+        .map(|(i, distance)| {
+            if i > 0 {
+                if let (Some(from), Some(to)) = (points[i - 1].elevation, points[i].elevation) {
+                    ascent += (to - from).max(0.0);
+                }
+            }
+            distance + 10.0 * ascent
+        })
+        .collect()
+}
+
+/// Calculates the average speed along all waypoints in the collection, which is the distance divided by the elapsed time.
+/// Returns the result in Meter per second or None, if no time elapsed.
+///
+/// ```
+/// use gpx::Waypoint;
+/// use geo::Point;
+/// use gpx_split::loc::*;
+/// use time::macros::datetime;
+/// use approx_eq::assert_approx_eq;
+///
+/// let mut p1 = Waypoint::new(Point::new(13.408056, 52.5186118));
+/// p1.time = Some(datetime!(2024-01-06 16:00 UTC).into());
+/// let mut p2 = Waypoint::new(Point::new(13.418056, 52.5186118));
+/// p2.time = Some(datetime!(2024-01-06 16:10 UTC).into());
+///
+/// assert_approx_eq!(distance(&p1, &p2) / 600.0, speed(&[p1, p2]).unwrap());
+/// ```
+#[must_use]
+pub fn speed(points: &[Waypoint]) -> Option<f64> {
+    let seconds = elapsed(points).as_seconds_f64();
+    (seconds > 0.0).then(|| distance_all(points) / seconds)
+}
+
+/// Calculates the cumulative ascent and descent of all waypoints in the collection.
+/// Changes of the elevation below the threshold are considered as noise and ignored,
+/// waypoints without an elevation are skipped.
+/// Returns the ascent and the descent in Meter.
+///
+/// ```
+/// use gpx::Waypoint;
+/// use geo::Point;
+/// use gpx_split::loc::*;
+///
+/// let points: Vec<Waypoint> = [100.0, 101.0, 100.5, 110.0, 104.0]
+///     .iter()
+///     .map(|ele| {
+///         let mut p = Waypoint::new(Point::new(0.0, 0.0));
+///         p.elevation = Some(*ele);
+///         p
+///     })
+///     .collect();
+/// assert_eq!((10.0, 6.0), elevation_change(&points, 2.0));
+/// ```
+#[must_use]
+pub fn elevation_change(points: &[Waypoint], threshold: f64) -> (f64, f64) {
+    let mut elevations = points.iter().filter_map(|p| p.elevation);
+    let Some(mut reference) = elevations.next() else {
+        return (0.0, 0.0);
+    };
+    let (mut ascent, mut descent) = (0.0, 0.0);
+    for elevation in elevations {
+        let diff = elevation - reference;
+        if diff.abs() >= threshold {
+            if diff > 0.0 {
+                ascent += diff;
+            } else {
+                descent -= diff;
+            }
+            reference = elevation;
+        }
+    }
+    (ascent, descent)
+}
+
 /// This will adjust the bounds of the metadata, if they are set.
 /// The new bounding box is a rectangle which contains min/max of x/y.
 ///
@@ -67,6 +189,42 @@ fn find_bounds(way_points: &[Waypoint]) -> Option<Rect<f64>> {
     ))
 }
 
+/// Returns the bounding box of the segment with x as longitude and y as latitude,
+/// which is padded by at least the distance in Meter on each side.
+///
+/// ```
+/// use gpx::Waypoint;
+/// use geo::Point;
+/// use gpx_split::loc::*;
+///
+/// let p1 = Waypoint::new(Point::new(13.40, 52.51));
+/// let p2 = Waypoint::new(Point::new(13.41, 52.52));
+/// let bounds = padded_bounds((&p1, &p2), 100.0);
+/// assert!(distance(&p1, &Waypoint::new(Point::new(bounds.min().x, 52.51))) > 100.0);
+/// assert!(distance(&p2, &Waypoint::new(Point::new(13.41, bounds.max().y))) > 100.0);
+/// ```
+#[must_use]
+pub fn padded_bounds(segment: (&Waypoint, &Waypoint), padding: f64) -> Rect<f64> {
+    //a degree of latitude is at least 110574 m, a degree of longitude shrinks with the cosine of the latitude
+    const DEGREE: f64 = 110_000.0;
+    let (p1, p2) = (segment.0.point(), segment.1.point());
+    let lat_padding = padding / DEGREE;
+    let (min_lat, max_lat) = (
+        p1.y().min(p2.y()) - lat_padding,
+        p1.y().max(p2.y()) + lat_padding,
+    );
+    let max_abs_lat = min_lat.abs().max(max_lat.abs());
+    let lon_padding = if max_abs_lat < 89.0 {
+        padding / (DEGREE * max_abs_lat.to_radians().cos())
+    } else {
+        360.0
+    };
+    Rect::new(
+        coord! { x: p1.x().min(p2.x()) - lon_padding, y: min_lat },
+        coord! { x: p1.x().max(p2.x()) + lon_padding, y: max_lat },
+    )
+}
+
 /// Collect the points (x, y) from the given way points
 ///
 fn collect_points(points: &[Waypoint]) -> Vec<Point<f64>> {
@@ -93,6 +251,7 @@ fn collect_points(points: &[Waypoint]) -> Vec<Point<f64>> {
 /// assert_approx_eq!(20.543903, ip.point().y());
 /// ```
 pub fn intercept(point: &Waypoint, geodesic: (&Waypoint, &Waypoint)) -> Waypoint {
+    const MAX_ITERATIONS: usize = 100;
     let geod = Geodesic::wgs84();
     //equatorial radius
     let radius: f64 = geod.a;
@@ -101,7 +260,8 @@ pub fn intercept(point: &Waypoint, geodesic: (&Waypoint, &Waypoint)) -> Waypoint
     let mut point_a = (geodesic.0.point().x(), geodesic.0.point().y());
     let point_b = (geodesic.1.point().x(), geodesic.1.point().y());
 
-    loop {
+    //the iteration converges quickly, but not for every point, e.g. when the azimuth is nearly a right angle
+    for _ in 0..MAX_ITERATIONS {
         let a_c: (f64, f64, f64, f64) = geod.inverse(point_a.0, point_a.1, point_c.0, point_c.1);
         let a_b: (f64, f64, f64, f64) = geod.inverse(point_a.0, point_a.1, point_b.0, point_b.1);
         //distance p_a to p_c
@@ -119,7 +279,7 @@ pub fn intercept(point: &Waypoint, geodesic: (&Waypoint, &Waypoint)) -> Waypoint
 
         let p_a2: (f64, f64, f64, f64) = geod.direct(point_a.0, point_a.1, a_b.1, s_ax);
 
-        if s_ax.abs() < 1e-2 {
+        if !s_ax.is_finite() || s_ax.abs() < 1e-2 {
             break;
         }
 
@@ -128,6 +288,108 @@ pub fn intercept(point: &Waypoint, geodesic: (&Waypoint, &Waypoint)) -> Waypoint
     Waypoint::new(Geopoint::new(point_a.0, point_a.1))
 }
 
+/// Creates a new waypoint on the geodesic from the first to the second waypoint,
+/// where the distance in Meter is measured from the first waypoint.
+/// The elevation and the time of the new waypoint are interpolated, if both waypoints have them.
+///
+/// ```
+/// use gpx::Waypoint;
+/// use geo::Point;
+/// use gpx_split::loc::*;
+/// use approx_eq::assert_approx_eq;
+///
+/// let p1 = Waypoint::new(Point::new(13.408056, 52.5186118));
+/// let p2 = Waypoint::new(Point::new(13.418056, 52.5186118));
+/// let p = interpolate(&p1, &p2, 100.0);
+/// assert_approx_eq!(100.0, distance(&p1, &p), 1e-6);
+/// assert_approx_eq!(distance(&p1, &p2) - 100.0, distance(&p, &p2), 1e-6);
+/// ```
+#[must_use]
+pub fn interpolate(p1: &Waypoint, p2: &Waypoint, dist: f64) -> Waypoint {
+    let geod = Geodesic::wgs84();
+    let (lat1, lon1) = (p1.point().y(), p1.point().x());
+    let (lat2, lon2) = (p2.point().y(), p2.point().x());
+    let (total, azi, _, _): (f64, f64, f64, f64) = geod.inverse(lat1, lon1, lat2, lon2);
+    let (lat, lon): (f64, f64) = geod.direct(lat1, lon1, azi, dist);
+
+    let fraction = if total > 0.0 { dist / total } else { 0.0 };
+    let mut point = Waypoint::new(Geopoint::new(lon, lat));
+    point.elevation = p1
+        .elevation
+        .zip(p2.elevation)
+        .map(|(e1, e2)| e1 + (e2 - e1) * fraction);
+    point.time = timestamp(p1)
+        .zip(timestamp(p2))
+        .map(|(t1, t2)| (t1 + (t2 - t1) * fraction).into());
+    point
+}
+
+/// Returns the point where two segments cross each other, if they do.
+/// Touching segments, e.g. with a common endpoint, are not crossing.
+/// The crossing is calculated on the plane of longitude and latitude, which is precise enough for short segments.
+/// The returned point is on the second segment, where the elevation and the time are interpolated.
+///
+/// ```
+/// use gpx::Waypoint;
+/// use geo::Point;
+/// use gpx_split::loc::*;
+/// use approx_eq::assert_approx_eq;
+///
+/// let a = (&Waypoint::new(Point::new(13.40, 52.51)), &Waypoint::new(Point::new(13.42, 52.51)));
+/// let b = (&Waypoint::new(Point::new(13.41, 52.50)), &Waypoint::new(Point::new(13.41, 52.52)));
+/// let p = crossing(a, b).unwrap();
+/// assert_approx_eq!(13.41, p.point().x(), 1e-6);
+/// assert_approx_eq!(52.51, p.point().y(), 1e-4);
+/// ```
+#[must_use]
+pub fn crossing(
+    segment_a: (&Waypoint, &Waypoint),
+    segment_b: (&Waypoint, &Waypoint),
+) -> Option<Waypoint> {
+    let line = |segment: (&Waypoint, &Waypoint)| Line::new(segment.0.point(), segment.1.point());
+    match line_intersection(line(segment_a), line(segment_b))? {
+        LineIntersection::SinglePoint {
+            intersection,
+            is_proper: true,
+        } => {
+            let mut point = Waypoint::new(Geopoint::from(intersection));
+            let dist = distance(segment_b.0, &point);
+            //less than a millimeter away from an endpoint is touching, e.g. at a previous crossing point
+            if dist < 0.001 || distance(&point, segment_b.1) < 0.001 {
+                return None;
+            }
+            let interpolated = interpolate(segment_b.0, segment_b.1, dist);
+            point.elevation = interpolated.elevation;
+            point.time = interpolated.time;
+            Some(point)
+        }
+        _ => None,
+    }
+}
+
+/// Returns true if the point is inside the area, which is enclosed by the ring of waypoints.
+/// The ring is closed automatically, when the last waypoint is not the first one.
+///
+/// ```
+/// use gpx::Waypoint;
+/// use geo::Point;
+/// use gpx_split::loc::*;
+///
+/// let ring = [
+///     Waypoint::new(Point::new(0.0, 0.0)),
+///     Waypoint::new(Point::new(1.0, 0.0)),
+///     Waypoint::new(Point::new(1.0, 1.0)),
+///     Waypoint::new(Point::new(0.0, 1.0)),
+/// ];
+/// assert!(is_inside(&Waypoint::new(Point::new(0.5, 0.5)), &ring));
+/// assert!(!is_inside(&Waypoint::new(Point::new(1.5, 0.5)), &ring));
+/// ```
+#[must_use]
+pub fn is_inside(point: &Waypoint, ring: &[Waypoint]) -> bool {
+    let polygon = Polygon::new(LineString::from(collect_points(ring)), vec![]);
+    polygon.contains(&point.point())
+}
+
 /// Returns true if the point is on the segment or behind one of the endpoints of the segement
 /// with an allowed maximum distance, otherwise false.
 /// The parameter max is the maximum distance allowed to be considered as "near" in meter.<br/>
@@ -166,6 +428,8 @@ mod tests {
     use approx_eq::assert_approx_eq;
     use geo_types::{coord, Point, Rect};
     use gpx::{Gpx, Metadata, Waypoint};
+    use time::macros::datetime;
+    use time::OffsetDateTime;
 
     use super::*;
 
@@ -189,6 +453,81 @@ mod tests {
         assert_approx_eq!(distance, 961.8288);
     }
 
+    #[test]
+    fn distances_along_array() {
+        let point_0 = waypoint(-73.9761399, 40.7767644);
+        let point_1 = waypoint(-73.9673991, 40.771209);
+        let distances = distances_along(&[point_0.clone(), point_1, point_0]);
+        assert_eq!(3, distances.len());
+        assert_eq!(0.0, distances[0]);
+        assert_approx_eq!(distances[1], 961.8288);
+        assert_approx_eq!(distances[2], 2.0 * 961.8288);
+        assert!(distances_along(&[]).is_empty());
+    }
+
+    #[test]
+    fn inside_ring() {
+        let ring = [
+            waypoint(0.0, 0.0),
+            waypoint(0.01, 0.0),
+            waypoint(0.01, 0.01),
+            waypoint(0.0, 0.01),
+            waypoint(0.0, 0.0),
+        ];
+        assert!(is_inside(&waypoint(0.005, 0.005), &ring));
+        assert!(!is_inside(&waypoint(0.015, 0.005), &ring));
+        assert!(!is_inside(&waypoint(0.005, 0.005), &ring[..2]));
+    }
+
+    #[test]
+    fn crossing_segments() {
+        let (a0, a1) = (waypoint(0.0, 0.0), waypoint(0.01, 0.0));
+        let mut b0 = waypoint(0.005, -0.01);
+        b0.elevation = Some(100.0);
+        let mut b1 = waypoint(0.005, 0.01);
+        b1.elevation = Some(200.0);
+        let point = crossing((&a0, &a1), (&b0, &b1)).unwrap();
+        assert_approx_eq!(0.005, point.point().x(), 1e-6);
+        assert_approx_eq!(150.0, point.elevation.unwrap(), 1e-3);
+        //touching at an endpoint and parallel segments are not crossing
+        assert!(crossing((&a0, &a1), (&a1, &b1)).is_none());
+        assert!(crossing((&a0, &a1), (&waypoint(0.0, 0.001), &waypoint(0.01, 0.001))).is_none());
+    }
+
+    fn elevated(elevations: &[Option<f64>]) -> Vec<Waypoint> {
+        elevations
+            .iter()
+            .map(|ele| {
+                let mut p = waypoint(0.0, 0.0);
+                p.elevation = *ele;
+                p
+            })
+            .collect()
+    }
+
+    #[test]
+    fn elevation_change_without_elevation() {
+        assert_eq!((0.0, 0.0), elevation_change(&[], 1.0));
+        assert_eq!((0.0, 0.0), elevation_change(&elevated(&[None, None]), 1.0));
+    }
+
+    #[test]
+    fn elevation_change_ignores_noise() {
+        let points = elevated(&[
+            Some(100.0),
+            Some(100.4),
+            Some(99.8),
+            None,
+            Some(100.3),
+            Some(103.0),
+            Some(102.5),
+            Some(98.0),
+        ]);
+        assert_eq!((3.0, 5.0), elevation_change(&points, 1.0));
+        let points = elevated(&[Some(100.0), Some(103.0), Some(100.5)]);
+        assert_eq!((3.0, 2.5), elevation_change(&points, 0.0));
+    }
+
     #[test]
     fn no_waypoints_no_bounds() {
         let mut meta = Metadata::default();
@@ -245,6 +584,39 @@ mod tests {
         assert_approx_eq!(30.9607975, dist_p_ip);
     }
 
+    #[test]
+    fn interpolate_elevation_and_time() {
+        let mut p1 = waypoint(12.7221600, 61.6349280);
+        p1.elevation = Some(700.0);
+        p1.time = Some(datetime!(2021-07-15 11:00 UTC).into());
+        let mut p2 = waypoint(12.7321600, 61.6349280);
+        p2.elevation = Some(800.0);
+        p2.time = Some(datetime!(2021-07-15 12:00 UTC).into());
+        let quarter = distance(&p1, &p2) / 4.0;
+
+        let p = interpolate(&p1, &p2, quarter);
+
+        assert_approx_eq!(quarter, distance(&p1, &p), 1e-6);
+        assert_approx_eq!(725.0, p.elevation.unwrap());
+        let time = OffsetDateTime::from(p.time.unwrap());
+        assert_eq!(datetime!(2021-07-15 11:15 UTC), time);
+    }
+
+    #[test]
+    fn interpolate_without_elevation_and_time() {
+        let p1 = waypoint(12.7221600, 61.6349280);
+        let mut p2 = waypoint(12.7321600, 61.6349280);
+        p2.elevation = Some(800.0);
+        p2.time = Some(datetime!(2021-07-15 12:00 UTC).into());
+
+        let p = interpolate(&p1, &p2, 10.0);
+
+        assert_eq!(None, p.elevation);
+        assert_eq!(None, p.time);
+        let same = interpolate(&p1, &p1, 0.0);
+        assert_eq!(p1.point(), same.point());
+    }
+
     #[test]
     fn near_segment() {
         let segment = (&waypoint(0.0, 0.5), &waypoint(0.0, 1.0));
@@ -1,8 +1,11 @@
 use gpx::errors::GpxError;
 use gpx::read;
 use gpx::write;
-use gpx::Gpx;
+use gpx::{Gpx, Waypoint};
 use log::debug;
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::fs;
 use std::fs::File;
 use std::io::{BufReader, Error, ErrorKind};
 
@@ -19,37 +22,102 @@ pub fn read_gpx(path: &str) -> Result<Gpx> {
     }
 }
 
+/// Reads the points to split at from the given path. These are the waypoints,
+/// the points of the routes and the points of the tracks, which have a name.
+pub fn read_split_points(path: &str) -> Result<Vec<Waypoint>> {
+    let gpx = read_gpx(path).map_err(|err| {
+        Error::new(
+            err.kind(),
+            format!("can't read file with splitting points {path}: {err}"),
+        )
+    })?;
+    let route_points = gpx.routes.into_iter().flat_map(|route| route.points);
+    let track_points = gpx
+        .tracks
+        .into_iter()
+        .flat_map(|track| track.segments)
+        .flat_map(|segment| segment.points)
+        .filter(|point| point.name.is_some());
+    Ok(gpx
+        .waypoints
+        .into_iter()
+        .chain(route_points)
+        .chain(track_points)
+        .collect())
+}
+
+/// Merges the splitting points of several sources, e.g. files and coordinates, in their order.
+/// The same location in several sources is a single splitting point,
+/// a location repeated in the same source is kept.
+#[must_use]
+pub fn merge_split_points(sources: Vec<Vec<Waypoint>>) -> Vec<Waypoint> {
+    let key = |point: &Waypoint| {
+        let (x, y) = point.point().x_y();
+        (x.to_bits(), y.to_bits())
+    };
+    let mut seen = HashSet::new();
+    let mut points = vec![];
+    for source in sources {
+        let keys: Vec<(u64, u64)> = source.iter().map(key).collect();
+        points.extend(
+            source
+                .into_iter()
+                .zip(&keys)
+                .filter(|(_, key)| !seen.contains(*key))
+                .map(|(point, _)| point),
+        );
+        seen.extend(keys);
+    }
+    points
+}
+
 /// Writes the Gpx into a new file pased on the given path
-/// while appending the counter to the filename.
-pub fn write_gpx(mut gpx: Gpx, path: &str, counter: usize) -> Result<()> {
-    gpx = update_metadata_name(gpx, counter);
-    let path = create_path(path, counter)?;
-    let file = File::create(&path)?;
-    let res = write(&gpx, file);
-    match res {
-        Ok(()) => {
-            debug!("wrote file {}", path);
-            Ok(())
-        }
-        Err(gpx_err) => Err(to_error(&gpx_err)),
+/// while appending the suffix, e.g. a counter, to the filename.
+/// Returns the size of the file in bytes.
+///
+/// # Errors
+///
+/// If the Gpx can't be written or its size is above the budget, if there is one.
+/// The file is not created, when the size is above the budget.
+pub fn write_gpx(mut gpx: Gpx, path: &str, suffix: &str, budget: Option<u64>) -> Result<u64> {
+    gpx = update_metadata_name(gpx, suffix);
+    let path = create_path(path, suffix)?;
+    let mut buffer = Vec::new();
+    write(&gpx, &mut buffer).map_err(|gpx_err| to_error(&gpx_err))?;
+    let size = buffer.len() as u64;
+    if let Some(max) = budget.filter(|max| size > *max) {
+        return Err(Error::new(
+            ErrorKind::FileTooLarge,
+            format!("the file {path} has {size} bytes, which is above the budget of {max} bytes"),
+        ));
     }
+    fs::write(&path, buffer)?;
+    debug!("wrote file {} with {} bytes", path, size);
+    Ok(size)
+}
+
+/// Returns the size in bytes of the Gpx, when it is written into a file.
+pub fn serialized_size(gpx: &Gpx) -> Result<u64> {
+    let mut buffer = Vec::new();
+    write(gpx, &mut buffer).map_err(|gpx_err| to_error(&gpx_err))?;
+    Ok(buffer.len() as u64)
 }
 
-fn update_metadata_name(mut gpx: Gpx, counter: usize) -> Gpx {
+fn update_metadata_name(mut gpx: Gpx, suffix: &str) -> Gpx {
     gpx.metadata = gpx.metadata.map(|mut meta| {
-        meta.name = append_index(meta.name, counter);
+        meta.name = append_index(meta.name, suffix);
         meta
     });
     gpx
 }
 
-/// Appends an integer to the end of the name.
-pub fn append_index(name: Option<String>, index: usize) -> Option<String> {
+/// Appends an index, e.g. an integer, to the end of the name.
+pub fn append_index<T: Display>(name: Option<String>, index: T) -> Option<String> {
     name.map(|n| format!("{n} #{index}"))
 }
 
 /// creates a new path to a file
-fn create_path(path: &str, counter: usize) -> Result<String> {
+fn create_path(path: &str, suffix: &str) -> Result<String> {
     let parts: Vec<&str> = path.rsplitn(2, '.').collect();
     if parts.len() != 2 {
         return Err(Error::new(
@@ -58,7 +126,7 @@ fn create_path(path: &str, counter: usize) -> Result<String> {
         ));
     }
     //new file name would be like foo_1.gpx
-    let name = format!("{}_{}.{}", parts[1], counter, parts[0]);
+    let name = format!("{}_{}.{}", parts[1], suffix, parts[0]);
     Ok(name)
 }
 
@@ -69,6 +137,7 @@ fn to_error(gpx_err: &GpxError) -> Error {
 
 #[cfg(test)]
 mod tests {
+    use geo_types::Point;
     use gpx::{Gpx, Metadata};
 
     use super::*;
@@ -83,14 +152,61 @@ mod tests {
             ..Default::default()
         };
 
-        gpx = update_metadata_name(gpx, 1);
+        gpx = update_metadata_name(gpx, "1");
         let res = gpx.metadata.unwrap().name.unwrap();
         assert_eq!("bar #1", res);
     }
 
+    #[test]
+    fn read_split_points_of_route() {
+        let points = read_split_points("target/debug/pois_route.gpx").unwrap();
+        let names: Vec<String> = points.into_iter().filter_map(|p| p.name).collect();
+        assert_eq!(vec!["Stop1", "Stop2"], names);
+    }
+
+    #[test]
+    fn read_split_points_of_track() {
+        //only the named points of a track
+        let points = read_split_points("target/debug/track_loc.gpx").unwrap();
+        assert!(points.is_empty());
+    }
+
+    #[test]
+    fn merge_split_points_of_sources() {
+        let point = |x: f64, name: &str| {
+            let mut point = Waypoint::new(Point::new(x, 0.0));
+            point.name = Some(name.to_string());
+            point
+        };
+        //the location repeated in the same source is kept, the one in the second source is not
+        let merged = merge_split_points(vec![
+            vec![point(1.0, "A"), point(2.0, "B"), point(1.0, "C")],
+            vec![point(2.0, "D"), point(3.0, "E")],
+        ]);
+        let names: Vec<String> = merged.into_iter().filter_map(|p| p.name).collect();
+        assert_eq!(vec!["A", "B", "C", "E"], names);
+    }
+
+    #[test]
+    fn size_of_written_file() {
+        let mut gpx = read_gpx("target/debug/track_points.gpx").unwrap();
+        gpx.metadata = None;
+        let size = serialized_size(&gpx).unwrap();
+        assert_eq!(
+            size,
+            write_gpx(gpx, "target/debug/size.gpx", "0", None).unwrap()
+        );
+    }
+
     #[test]
     fn create_path_with_counter() {
-        let res = create_path("foo/bar.gpx", 1).unwrap();
+        let res = create_path("foo/bar.gpx", "1").unwrap();
         assert_eq!("foo/bar_1.gpx", res);
     }
+
+    #[test]
+    fn create_path_with_date() {
+        let res = create_path("foo/bar.gpx", "2021-07-15").unwrap();
+        assert_eq!("foo/bar_2021-07-15.gpx", res);
+    }
 }
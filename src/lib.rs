@@ -1,8 +1,14 @@
+/// Provides functions to handle the timestamps of waypoints.
+pub mod clock;
+/// Presets for devices with known limits.
+pub mod device;
 mod geo;
 /// Input and output functions.
 pub mod io;
 /// Conditions when a limit is exceed and the track or rooute needs to be splitted.
 pub mod limit;
+/// Models to estimate the travel time of a track or route.
+pub mod pace;
 /// The splitting logic for a track or route.
 pub mod split;
 
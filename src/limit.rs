@@ -1,19 +1,633 @@
-use gpx::Waypoint;
+use geo::Point;
+use gpx::{Gpx, GpxVersion, Route, Track, TrackSegment, Waypoint};
+use humantime::format_duration;
 use log::debug;
+use log::info;
 use log::trace;
-use std::collections::BTreeMap;
+use log::warn;
+use regex::Regex;
+use rstar::primitives::{GeomWithData, Rectangle};
+use rstar::{RTree, AABB};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::{self, Error, ErrorKind};
+use std::mem;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use time::OffsetDateTime;
 
-use crate::geo::{distance, distance_all, intercept, is_near_segment};
-use crate::io::read_gpx;
+use crate::clock::{elapsed, solar_date, sunset, timestamp, Zone};
+use crate::geo::{
+    crossing, distance, distance_all, distances_along, efforts_along, elevation_change, intercept,
+    interpolate, is_inside, is_near_segment, padded_bounds, speed,
+};
+use crate::io::{merge_split_points, read_gpx, read_split_points, serialized_size};
+use crate::pace::Pace;
+
+/// Position where the points are split, when a limit is exceeded.
+#[derive(Debug, PartialEq)]
+pub enum Split {
+    /// the point at the index ends the current chunk and is also the first point of the next chunk
+    At(usize),
+    /// the current chunk ends before the point at the index, which is the first point of the next chunk
+    Before(usize),
+    /// the current chunk ends before the point at the first index, the next chunk starts with the point
+    /// at the second index, the points in between are in none of the chunks
+    Skip(usize, usize),
+}
+
+/// Points written into the file of a chunk besides the points checked by a limit. Only the limits of a file,
+/// e.g. its number of points or its size, count them.
+#[derive(Clone, Copy, Debug)]
+pub struct Carried<'a> {
+    /// the points at the end of the previous chunk, which are written before the points of the chunk
+    pub points: &'a [Waypoint],
+    /// false, when the first point of the chunk is not written, because it ends the previous chunk without overlap
+    pub shared: bool,
+}
+
+impl Carried<'_> {
+    // Returns the number of points written into the file besides the checked points, which can be negative.
+    #[allow(clippy::cast_possible_wrap)]
+    fn count(&self) -> isize {
+        self.points.len() as isize - isize::from(!self.shared)
+    }
+}
+
+impl Default for Carried<'_> {
+    fn default() -> Self {
+        Carried {
+            points: &[],
+            shared: true,
+        }
+    }
+}
+
+/// Measure for the size of the parts, when the points are split into balanced parts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Measure {
+    /// the number of points
+    Points,
+    /// the length in meter
+    Length,
+}
+
+/// Policy to reuse a location for splitting after its first use, e.g. for the trailhead of an out-and-back route.
+/// A location is only reused on another pass, which starts when the points are no longer near the location.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reuse {
+    /// each location is used only once
+    Once,
+    /// a location is used again, when the travel since its last use is at least the distance in meter
+    After(u32),
+    /// a location is used again on every pass
+    Always,
+    /// each location is used only once in the order of the file, only the next unused one can split
+    Ordered,
+}
+
+impl FromStr for Reuse {
+    type Err = String;
+
+    /// Parses either once, always or the distance in meter to travel before reusing a location.
+    fn from_str(reuse: &str) -> Result<Self, Self::Err> {
+        match reuse {
+            "once" => Ok(Reuse::Once),
+            "always" => Ok(Reuse::Always),
+            "ordered" => Ok(Reuse::Ordered),
+            _ => reuse.parse().map(Reuse::After).map_err(|_| {
+                format!("expected once, always, ordered or a distance in meter: {reuse}")
+            }),
+        }
+    }
+}
+
+/// Filter for the locations to split at, where all given criteria must match.
+#[derive(Debug, Default)]
+pub struct Filter {
+    /// the symbol of a location, e.g. Campground, case is ignored
+    pub symbol: Option<String>,
+    /// the type of a location, case is ignored
+    pub kind: Option<String>,
+    /// the pattern for the name of a location
+    pub name: Option<Regex>,
+}
+
+impl Filter {
+    /// Returns true, if the location matches all criteria of the filter.
+    #[must_use]
+    pub fn matches(&self, point: &Waypoint) -> bool {
+        let equals = |expected: &Option<String>, actual: &Option<String>| match expected {
+            Some(expected) => actual
+                .as_ref()
+                .is_some_and(|actual| actual.eq_ignore_ascii_case(expected)),
+            None => true,
+        };
+        equals(&self.symbol, &point.symbol)
+            && equals(&self.kind, &point.type_)
+            && self.name.as_ref().is_none_or(|pattern| {
+                point
+                    .name
+                    .as_ref()
+                    .is_some_and(|name| pattern.is_match(name))
+            })
+    }
+}
+
+/// Locations to split at, which keep track of their use.
+/// A location can have its own distance to split, e.g. a lake, which is a token like radius=500
+/// in Meter in its comment or description.
+#[derive(Clone)]
+pub struct SplitPoints {
+    //the points, their radii and the index are shared by the copies, which are checked by a composite limit
+    points: Arc<Vec<Waypoint>>,
+    //the distance to split of each point, if it has its own
+    radii: Arc<Vec<Option<u32>>>,
+    //the largest of the own distances to split
+    max_radius: u32,
+    //spatial index with the coordinates and the index of each point
+    tree: Arc<RTree<GeomWithData<[f64; 2], usize>>>,
+    reuse: Reuse,
+    //the travel when a point was used last time and whether the points left it since then
+    used: HashMap<usize, (f64, bool)>,
+    //the used points, which the points did not leave yet
+    staying: Vec<usize>,
+    //the distance travelled along the points and the last one of them
+    travelled: f64,
+    last: Option<Point<f64>>,
+    //the index of the next point, when the points are used in order
+    next: usize,
+}
+
+impl SplitPoints {
+    /// Creates new locations to split at, which are reused according to the policy.
+    #[must_use]
+    pub fn new(points: Vec<Waypoint>, reuse: Reuse) -> Self {
+        let radii: Vec<Option<u32>> = points.iter().map(SplitPoints::radius_of).collect();
+        let max_radius = radii.iter().flatten().copied().max().unwrap_or_default();
+        let tree = RTree::bulk_load(
+            points
+                .iter()
+                .enumerate()
+                .map(|(index, point)| GeomWithData::new(point.point().x_y().into(), index))
+                .collect(),
+        );
+        SplitPoints {
+            points: Arc::new(points),
+            radii: Arc::new(radii),
+            max_radius,
+            tree: Arc::new(tree),
+            reuse,
+            used: HashMap::new(),
+            staying: vec![],
+            travelled: 0.0,
+            last: None,
+            next: 0,
+        }
+    }
+
+    /// Returns the number of locations.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Returns true, if there are no locations.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    // Reads the distance to split from a token like radius=500 in the comment or the description.
+    fn radius_of(point: &Waypoint) -> Option<u32> {
+        [&point.comment, &point.description]
+            .into_iter()
+            .flatten()
+            .find_map(|text| {
+                let (_, value) = text.split_once("radius=")?;
+                value
+                    .split(|c: char| !c.is_ascii_digit())
+                    .next()?
+                    .parse()
+                    .ok()
+            })
+    }
+
+    // Returns the distance to split of the point, which is either its own or the given one.
+    fn radius(&self, index: usize, dist: u32) -> u32 {
+        self.radii[index].unwrap_or(dist)
+    }
+
+    // Returns the sorted indices of the points with their distance to split, which could be near the segment.
+    fn candidates(&self, segment: (&Waypoint, &Waypoint), dist: u32) -> Vec<(usize, u32)> {
+        let max = self.max_radius.max(dist);
+        //an interception point can be up to the distance behind the end of the segment
+        let bounds = padded_bounds(segment, 2.0 * f64::from(max));
+        let envelope = AABB::from_corners(bounds.min().x_y().into(), bounds.max().x_y().into());
+        let mut candidates: Vec<(usize, u32)> = self
+            .tree
+            .locate_in_envelope(&envelope)
+            .map(|point| (point.data, self.radius(point.data, dist)))
+            .collect();
+        candidates.sort_unstable();
+        candidates
+    }
+
+    // Returns true, if one of the locations is within its distance to split of the point.
+    fn is_near(&self, point: &Waypoint, dist: u32) -> bool {
+        self.candidates((point, point), dist)
+            .into_iter()
+            .any(|(index, radius)| distance(&self.points[index], point) <= f64::from(radius))
+    }
+
+    // Adds the segment to the travel, unless it ends at the same point as the previous one.
+    fn travel(&mut self, segment: (&Waypoint, &Waypoint)) {
+        let end = segment.1.point();
+        if self.last != Some(end) {
+            self.travelled += distance(segment.0, segment.1);
+            self.last = Some(end);
+        }
+    }
+
+    // Marks the used points as left, which are not near the current segment.
+    fn leave(&mut self, near: &[usize]) {
+        let used = &mut self.used;
+        self.staying.retain(|index| {
+            let stays = near.contains(index);
+            if let (false, Some((_, left))) = (stays, used.get_mut(index)) {
+                *left = true;
+            }
+            stays
+        });
+    }
+
+    fn is_usable(&self, index: usize, dist: u32) -> bool {
+        match (self.used.get(&index).copied(), self.reuse) {
+            //the next point has to wait until we left the previous one, when it is at the same location
+            (None, Reuse::Ordered) => {
+                index == self.next
+                    && index.checked_sub(1).is_none_or(|previous| {
+                        distance(&self.points[previous], &self.points[index])
+                            > f64::from(self.radius(index, dist))
+                            || self.used.get(&previous).is_none_or(|(_, left)| *left)
+                    })
+            }
+            (None, _) => true,
+            (Some(_), Reuse::Once | Reuse::Ordered) => false,
+            (Some((_, left)), Reuse::Always) => left,
+            (Some((travelled, left)), Reuse::After(dist)) => {
+                left && self.travelled - travelled >= f64::from(dist)
+            }
+        }
+    }
+
+    fn use_point(&mut self, index: usize, interception: &Waypoint) {
+        self.used.insert(index, (self.travelled, false));
+        if !self.staying.contains(&index) {
+            self.staying.push(index);
+        }
+        self.last = Some(interception.point());
+        self.next = index + 1;
+    }
+
+    // Returns the points, which were never used in order.
+    fn skipped(&self) -> &[Waypoint] {
+        match self.reuse {
+            Reuse::Ordered => &self.points[self.next..],
+            _ => &[],
+        }
+    }
+}
+
+/// Stages of a multi-day tour, which end at the location with the smallest detour within a window of the length.
+#[derive(Clone)]
+pub struct Stages {
+    split_points: SplitPoints,
+    window: RangeInclusive<u32>,
+    detour: u32,
+    //the number of stages so far and the numbers of those without a location at their end
+    count: usize,
+    missing: Vec<usize>,
+}
+
+impl Stages {
+    /// Creates new stages with a window of the length in Meter,
+    /// which end at a location with a detour up to the given distance in Meter.
+    #[must_use]
+    pub fn new(points: Vec<Waypoint>, window: RangeInclusive<u32>, detour: u32) -> Self {
+        Stages {
+            split_points: SplitPoints::new(points, Reuse::Always),
+            window,
+            detour,
+            count: 0,
+            missing: vec![],
+        }
+    }
+
+    /// Returns the numbers of the stages, starting at 1, which end without a location.
+    #[must_use]
+    pub fn missing(&self) -> &[usize] {
+        &self.missing
+    }
+
+    // Finds the interception point with the smallest detour inside the window,
+    // together with the index of the segment where it is.
+    fn best(&self, points: &[Waypoint]) -> Option<(usize, Waypoint)> {
+        let along = distances_along(points);
+        let (min, max) = (
+            f64::from(*self.window.start()),
+            f64::from(*self.window.end()),
+        );
+        let mut best: Option<(i64, usize, Waypoint)> = None;
+        for (index, segment) in points.windows(2).enumerate() {
+            if along[index + 1] < min || along[index] > max {
+                continue;
+            }
+            let segment = (&segment[0], &segment[1]);
+            let candidates = self.split_points.candidates(segment, self.detour);
+            let map = Limit::interception_points(&self.split_points.points, &candidates, segment);
+            let found = map.into_iter().find(|(_, point)| {
+                (min..=max).contains(&(along[index] + distance(segment.0, point)))
+            });
+            if let Some(((detour, _), point)) = found {
+                if best
+                    .as_ref()
+                    .is_none_or(|(shortest, ..)| detour < *shortest)
+                {
+                    best = Some((detour, index, point));
+                }
+            }
+        }
+        best.map(|(_, index, point)| (index, point))
+    }
+}
+
+/// Schedule of the days, where each chunk starts at the time, which is reached at its first point.
+#[derive(Clone, Copy)]
+pub struct Schedule {
+    //the start of the current day
+    morning: OffsetDateTime,
+    //the time at the first point of the current chunk
+    start: OffsetDateTime,
+    //true, when the current chunk ends before the sunset, so the next chunk starts on the next day
+    night: bool,
+}
+
+impl Schedule {
+    // Moves on to the next chunk, which starts at the point at the index.
+    fn restart(&mut self, pace: Pace, points: &[Waypoint], index: usize) {
+        if self.night {
+            self.morning += time::Duration::DAY;
+            self.start = self.morning;
+            self.night = false;
+        } else {
+            self.start += pace.estimate(&points[..=index.min(points.len() - 1)]);
+        }
+    }
+}
+
+/// Budget for the size of a file in bytes, which is estimated while the points are added.
+#[derive(Clone)]
+pub struct Budget {
+    max: u64,
+    //the bytes of the data around the points, e.g. the metadata
+    reserved: u64,
+    //the size of a track with a single point, which is the base to estimate the size of another point
+    base: u64,
+    //the estimated size of the points
+    total: Tally<u64>,
+}
+
+impl Budget {
+    /// Creates a new budget of the maximum size of a file in bytes.
+    #[must_use]
+    pub fn new(max: u64) -> Self {
+        let base = Budget::track_size(&[Waypoint::default()]);
+        Budget {
+            max,
+            reserved: 0,
+            base,
+            total: Tally::default(),
+        }
+    }
+
+    // Estimates the size of the point in bytes, when it is written as a point of a track.
+    // A point of a route has two spaces less of indentation on each line, so this is a bit more than needed.
+    fn point_size(base: u64, point: &Waypoint) -> u64 {
+        Budget::track_size(&[Waypoint::default(), point.clone()]) - base
+    }
+
+    fn track_size(points: &[Waypoint]) -> u64 {
+        let mut segment = TrackSegment::new();
+        segment.points = points.to_vec();
+        let mut track = Track::new();
+        track.segments.push(segment);
+        let gpx = Gpx {
+            version: GpxVersion::Gpx11,
+            tracks: vec![track],
+            ..Gpx::default()
+        };
+        //writing into memory does not fail, except for invalid data, which will fail when the file is written
+        serialized_size(&gpx).unwrap_or_default()
+    }
+}
+
+/// State of a limit, which is built from the points of a chunk one point at a time.
+/// It is built again from the points, when these are not the points seen before and a new one,
+/// e.g. after a composite limit dropped a trial, or when it was reset at the start of a new chunk.
+#[derive(Clone, Default)]
+pub struct Tally<T> {
+    state: T,
+    //the number of points seen, None when the state needs to be built again
+    count: Option<usize>,
+}
+
+impl<T> Tally<T> {
+    // Returns the state of all points except the last one, which the caller adds to the state.
+    fn sync(&mut self, points: &[Waypoint], build: impl FnOnce(&[Waypoint]) -> T) -> &mut T {
+        let len = points.len();
+        if self.count.is_none_or(|count| count + 1 != len) {
+            self.state = build(&points[..len.saturating_sub(1)]);
+        }
+        self.count = Some(len);
+        &mut self.state
+    }
+
+    fn reset(&mut self) {
+        self.count = None;
+    }
+}
+
+/// Candidate for a stop, which is anchored at its first point. The stop lasts as long as the points
+/// stay within the radius of the anchor.
+#[derive(Clone, Default)]
+pub struct Stop {
+    //the index of the anchor in the points
+    anchor: Tally<usize>,
+}
+
+impl Stop {
+    // Finds the anchor of the last candidate, where each candidate starts at the first point outside the radius
+    // of the anchor before.
+    fn anchor(points: &[Waypoint], radius: f64) -> usize {
+        let mut anchor = 0;
+        for (index, point) in points.iter().enumerate() {
+            if distance(&points[anchor], point) > radius {
+                anchor = index;
+            }
+        }
+        anchor
+    }
+}
+
+/// Spatial index of the earlier points and segments of a chunk, which can close a loop.
+#[derive(Clone, Default)]
+pub struct Trail {
+    points: RTree<GeomWithData<[f64; 2], usize>>,
+    //the bounds of each segment with the index of its first point
+    segments: RTree<GeomWithData<Rectangle<[f64; 2]>, usize>>,
+}
+
+impl Trail {
+    // Creates a new index of the points and their segments.
+    fn new(points: &[Waypoint]) -> Self {
+        Trail {
+            points: RTree::bulk_load(
+                points
+                    .iter()
+                    .enumerate()
+                    .map(|(index, point)| GeomWithData::new(point.point().x_y().into(), index))
+                    .collect(),
+            ),
+            segments: RTree::bulk_load(
+                points
+                    .windows(2)
+                    .enumerate()
+                    .map(|(index, segment)| Trail::segment(&segment[0], &segment[1], index))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn segment(
+        from: &Waypoint,
+        to: &Waypoint,
+        index: usize,
+    ) -> GeomWithData<Rectangle<[f64; 2]>, usize> {
+        let rectangle = Rectangle::from_corners(from.point().x_y().into(), to.point().x_y().into());
+        GeomWithData::new(rectangle, index)
+    }
+
+    // Adds the last of the points and the segment to it.
+    fn push(&mut self, points: &[Waypoint]) {
+        let last = points.len() - 1;
+        self.points
+            .insert(GeomWithData::new(points[last].point().x_y().into(), last));
+        if last > 0 {
+            self.segments
+                .insert(Trail::segment(&points[last - 1], &points[last], last - 1));
+        }
+    }
+}
+
+/// Band of the speed for a mode of activity, e.g. walking up to 7 km/h.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Band {
+    /// the name of the mode
+    pub mode: String,
+    /// the maximum speed in km/h or None for the fastest mode
+    pub max_speed: Option<f64>,
+}
+
+impl Band {
+    // The band of the average speed of the points, where the speed beyond all bands belongs to the last one.
+    fn find(bands: &[Band], points: &[Waypoint]) -> Option<usize> {
+        let speed = speed(points)? * 3.6;
+        let index = bands
+            .iter()
+            .position(|band| band.max_speed.is_none_or(|max| speed <= max));
+        index.or(bands.len().checked_sub(1))
+    }
+}
+
+impl FromStr for Band {
+    type Err = String;
+
+    /// Parses the name of the mode with an optional maximum speed in km/h, e.g. walk:7 or motor.
+    fn from_str(band: &str) -> Result<Self, Self::Err> {
+        let (mode, max_speed) = match band.split_once(':') {
+            Some((mode, max)) => {
+                let max: f64 = max
+                    .parse()
+                    .map_err(|_| format!("invalid maximum speed: {max}"))?;
+                (mode, Some(max))
+            }
+            None => (band, None),
+        };
+        if mode.is_empty() {
+            return Err(format!("missing mode of speed band: {band}"));
+        }
+        Ok(Band {
+            mode: mode.to_string(),
+            max_speed,
+        })
+    }
+}
 
 /// Checks if the points exceed a defined limit.
+#[derive(Clone)]
 pub enum Limit {
     /// strategy to check limit based on the number of points
     Points(u32),
     /// strategy to check limit based on the length in meter of the sum of the distances between the points
     Length(u32),
+    /// strategy to check limit based on the length in meter, where the last point is exactly at the length
+    StrictLength(u32),
     /// strategy to check limit based on the distance in meter to the nearest location
-    Location(Box<Vec<Waypoint>>, u32),
+    Location(Box<SplitPoints>, u32),
+    /// strategy to check limit based on the length in meter, where a stage ends at the location with the smallest detour
+    /// once the length is within a window
+    Stage(Box<Stages>),
+    /// strategy to check limit based on the estimated size of the file in bytes, which contains the points
+    Size(Box<Budget>),
+    /// strategy to check limit based on crossing a gate, which is a line between the two points of a route
+    Gate(Box<Vec<Route>>),
+    /// strategy to check limit based on entering or leaving an area, which is enclosed by the points of a route
+    Fence(Box<Vec<Route>>),
+    /// strategy to check limit based on a change of the mode, which is the band of the speed averaged over a duration
+    Mode(Vec<Band>, Duration),
+    /// strategy to check limit based on the elapsed time between the first and the last point with a timestamp,
+    /// with the number of points without a timestamp in the current chunk
+    Duration(Duration, usize),
+    /// strategy to check limit based on the travel time, which is estimated from the distance and the elevation
+    Pace(Pace, Duration),
+    /// strategy to check limit based on the sunset at the points, which are reached at a time estimated from the start
+    /// of the current chunk, where the safety margin before the sunset is kept, or no schedule, when it was too late
+    Daylight(Pace, Option<Schedule>, Duration),
+    /// strategy to check limit based on the time between two consecutive points with a timestamp
+    Gap(Duration),
+    /// strategy to check limit based on the cumulative ascent and optional descent in meter,
+    /// where changes below the noise threshold in meter are ignored
+    Elevation(u32, Option<u32>, u32),
+    /// strategy to check limit based on the local calendar day of the points in a time zone
+    Day(Zone),
+    /// strategy to check limit based on a stop, where the points stay within a radius in meter for a minimum duration
+    Dwell(u32, Duration, Stop),
+    /// strategy to check limit based on a loop, where the points cross themselves or return within a distance in meter
+    Loop(u32, Box<Tally<Trail>>),
+    /// strategy to split the points into the given number of parts with nearly equal size
+    Parts(Measure, u32),
+    /// strategy to split the points into the fewest parts with nearly equal size, where no part is above the maximum
+    Balance(Measure, u32),
+    /// strategy to split the points into the given number of parts with the least variance of the effort,
+    /// where the cuts are optionally restricted to points within a distance in meter of a location
+    Optimal(u32, Option<(Box<SplitPoints>, u32)>),
+    /// strategy to check limit based on other limits, where the first one exceeded triggers the split
+    Any(Vec<Limit>),
+    /// strategy to check limit based on other limits, where all of them need to be exceeded to split
+    All(Vec<Limit>),
 }
 
 impl Limit {
@@ -31,37 +645,1072 @@ impl Limit {
         Limit::Length(max_length)
     }
 
-    /// Creates a new limit for a distance to locations.
-    #[allow(clippy::missing_panics_doc)]
+    /// Creates a new limit of length in meter, where a chunk ends exactly at the length.
+    /// The last point of the chunk is interpolated between the points before and after the length.
+    #[must_use]
+    pub fn strict_length(max_length: u32) -> Self {
+        debug!("exact length between points: {}", max_length);
+        Limit::StrictLength(max_length)
+    }
+
+    /// Creates a new limit of elapsed time.
+    #[must_use]
+    pub fn duration(max_duration: Duration) -> Self {
+        debug!("maximum duration between points: {:?}", max_duration);
+        Limit::Duration(max_duration, 0)
+    }
+
+    /// Creates a new limit of the travel time, which is estimated by the model, e.g. for a planned route without timestamps.
+    #[must_use]
+    pub fn pace(pace: Pace, max_duration: Duration) -> Self {
+        debug!(
+            "maximum estimated travel time: {:?} by {}",
+            max_duration, pace
+        );
+        Limit::Pace(pace, max_duration)
+    }
+
+    /// Creates a new limit, which ends the chunk at the last point reached before the sunset minus the margin.
+    /// The time to reach a point is estimated by the model from the start,
+    /// the next chunk starts at the same time of the following day. When another limit splits the points,
+    /// the next chunk starts at the time, which is estimated for its first point.
+    /// The sunset is calculated for each point, there is no split when the sun does not set, e.g. near the poles.
+    /// There is no split either, when the start is already too late for the sunset at the first point.
     #[must_use]
-    pub fn location(waypoint_file: &str, distance: u32) -> Self {
+    pub fn daylight(start: OffsetDateTime, pace: Pace, margin: Duration) -> Self {
+        debug!(
+            "start of the first day: {}, travel time by {}, margin before the sunset: {:?}",
+            start, pace, margin
+        );
+        let schedule = Schedule {
+            morning: start,
+            start,
+            night: false,
+        };
+        Limit::Daylight(pace, Some(schedule), margin)
+    }
+
+    /// Creates a new limit of the time between two consecutive points with a timestamp, points without one are skipped.
+    #[must_use]
+    pub fn gap(max_gap: Duration) -> Self {
+        debug!("maximum time gap between points: {:?}", max_gap);
+        Limit::Gap(max_gap)
+    }
+
+    /// Creates a new limit of the cumulative ascent and optional descent in meter.
+    /// Changes of the elevation below the noise threshold are ignored, to compensate the jitter of GPS elevations.
+    #[must_use]
+    pub fn elevation(max_ascent: u32, max_descent: Option<u32>, noise: u32) -> Self {
+        debug!(
+            "maximum ascent: {}, maximum descent: {:?}",
+            max_ascent, max_descent
+        );
+        debug!("noise threshold of the elevation: {}", noise);
+        Limit::Elevation(max_ascent, max_descent, noise)
+    }
+
+    /// Creates a new limit for a distance to locations, which are read from the file and match the filter,
+    /// see [`read_split_points`] for the points used.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error, if the file can't be read or contains no matching location.
+    pub fn location(waypoint_file: &str, distance: u32, filter: &Filter) -> io::Result<Self> {
         trace!(
             "reading waypoints for splitting at location from: {}",
             waypoint_file
         );
-        //there is nothing much we can do here, just give up with a helpful error message
-        let gpx = read_gpx(waypoint_file).expect("can't read file with splitting points");
+        let waypoints = read_split_points(waypoint_file)?;
+        Limit::locations(vec![waypoints], distance, Reuse::Once, filter)
+    }
+
+    /// Creates a new limit for a distance to the given locations, which match the filter
+    /// and are reused according to the policy. The locations of the sources are merged,
+    /// see [`merge_split_points`], only the ordered ones are all kept.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error, if no location matches the filter.
+    pub fn locations(
+        sources: Vec<Vec<Waypoint>>,
+        distance: u32,
+        reuse: Reuse,
+        filter: &Filter,
+    ) -> io::Result<Self> {
         debug!("minimum distance for location to split: {}", distance);
-        let waypoints = gpx.waypoints;
+        let waypoints = Limit::matching(sources, filter, reuse == Reuse::Ordered)?;
+        debug!("reuse of the waypoints: {:?}", reuse);
+        Ok(Limit::Location(
+            Box::new(SplitPoints::new(waypoints, reuse)),
+            distance,
+        ))
+    }
+
+    /// Creates a new limit for stages of a multi-day tour, e.g. about 80 km which end at an accommodation.
+    /// When the length passes the window, the stage ends at the location with the smallest detour
+    /// from the points inside the window. Without such a location, it ends at the maximum of the window.
+    /// The locations of the sources are merged, see [`merge_split_points`].
+    ///
+    /// # Errors
+    ///
+    /// Will return an error, if no location matches the filter.
+    pub fn stages(
+        sources: Vec<Vec<Waypoint>>,
+        window: RangeInclusive<u32>,
+        detour: u32,
+        filter: &Filter,
+    ) -> io::Result<Self> {
+        debug!("window of the length of a stage: {:?}", window);
+        debug!("maximum detour to a location: {}", detour);
+        let waypoints = Limit::matching(sources, filter, false)?;
+        Ok(Limit::Stage(Box::new(Stages::new(
+            waypoints, window, detour,
+        ))))
+    }
+
+    // Returns the locations of the sources, which match the filter. These are merged after filtering,
+    // unless all of them are kept in order. It is an error, when there is none of them.
+    fn matching(
+        sources: Vec<Vec<Waypoint>>,
+        filter: &Filter,
+        ordered: bool,
+    ) -> io::Result<Vec<Waypoint>> {
+        debug!("filter for the waypoints: {:?}", filter);
+        let sources: Vec<Vec<Waypoint>> = sources
+            .into_iter()
+            .map(|source| {
+                source
+                    .into_iter()
+                    .filter(|point| filter.matches(point))
+                    .collect()
+            })
+            .collect();
+        let waypoints = if ordered {
+            sources.concat()
+        } else {
+            merge_split_points(sources)
+        };
         debug!("number of waypoints for splitting: {}", waypoints.len());
-        Limit::Location(Box::new(waypoints), distance)
+        if waypoints.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "there are no matching splitting points",
+            ));
+        }
+        Ok(waypoints)
+    }
+
+    /// Creates a new limit of the size of a file in bytes, e.g. for a device with an upload limit.
+    /// The size is estimated while the points are added, the chunk ends before it would be above the maximum.
+    /// The bytes around the points, e.g. the metadata, are reserved with [`Limit::reserve`].
+    #[must_use]
+    pub fn size(max_bytes: u64) -> Self {
+        debug!("maximum size of a file in bytes: {}", max_bytes);
+        Limit::Size(Box::new(Budget::new(max_bytes)))
+    }
+
+    /// Creates a new limit for crossing gates, e.g. a start/finish line or a checkpoint.
+    /// The gates are the routes with two points in the file, all other routes are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error, if the file can't be read or contains no gate.
+    pub fn gates(gate_file: &str) -> io::Result<Self> {
+        trace!(
+            "reading gates for splitting at crossings from: {}",
+            gate_file
+        );
+        let gates = Limit::shapes(gate_file, "gates", "with two points", |route| {
+            route.points.len() == 2
+        })?;
+        Ok(Limit::Gate(Box::new(gates)))
+    }
+
+    /// Creates a new limit for entering or leaving areas, e.g. a national park.
+    /// The areas are enclosed by the routes with at least three points in the file, all other routes are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error, if the file can't be read or contains no area.
+    pub fn fences(area_file: &str) -> io::Result<Self> {
+        trace!(
+            "reading areas for splitting at their boundary from: {}",
+            area_file
+        );
+        let areas = Limit::shapes(area_file, "areas", "with at least three points", |route| {
+            route.points.len() > 2
+        })?;
+        Ok(Limit::Fence(Box::new(areas)))
+    }
+
+    // Reads the routes of the file, which have the shape of a gate or an area. The other routes are skipped,
+    // it is an error, when there is none with the shape.
+    fn shapes(
+        file: &str,
+        what: &str,
+        shape: &str,
+        keep: fn(&Route) -> bool,
+    ) -> io::Result<Vec<Route>> {
+        let gpx = read_gpx(file).map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!("can't read file with {what} {file}: {err}"),
+            )
+        })?;
+        let (kept, others): (Vec<Route>, Vec<Route>) = gpx.routes.into_iter().partition(keep);
+        if !others.is_empty() {
+            warn!("skipping {} routes, which are not {}", others.len(), shape);
+        }
+        debug!("number of {} for splitting: {}", what, kept.len());
+        if kept.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("there are no {what} in {file}, which are routes {shape}"),
+            ));
+        }
+        Ok(kept)
+    }
+
+    /// Creates a new limit, which is exceeded when the points cross midnight in the time zone.
+    #[must_use]
+    pub fn day(zone: Zone) -> Self {
+        debug!("time zone for the calendar days: {}", zone);
+        Limit::Day(zone)
+    }
+
+    /// Creates a new limit, which is exceeded when the points leave a stop.
+    /// A stop consists of consecutive points within the radius in meter of its first point,
+    /// which lasts at least the given duration.
+    #[must_use]
+    pub fn dwell(radius: u32, min_duration: Duration) -> Self {
+        debug!(
+            "radius of a stop: {}, minimum duration of a stop: {:?}",
+            radius, min_duration
+        );
+        Limit::Dwell(radius, min_duration, Stop::default())
+    }
+
+    /// Creates a new limit, which is exceeded when the points close a loop.
+    /// A loop is closed, when the points cross an earlier segment or return within the distance in meter
+    /// to an earlier point after they went farther away. Note that going back the same way is also a loop.
+    #[must_use]
+    pub fn loops(distance: u32) -> Self {
+        debug!("distance to close a loop: {}", distance);
+        Limit::Loop(distance, Box::default())
+    }
+
+    /// Creates a new limit, which is exceeded when the mode of activity changes, e.g. from walking to cycling.
+    /// The mode is the band of the speed, which is averaged over the duration to smooth it.
+    /// The bands are in ascending order of their maximum speed.
+    #[must_use]
+    pub fn mode(bands: Vec<Band>, smoothing: Duration) -> Self {
+        debug!(
+            "speed bands: {:?}, smoothing of the speed: {:?}",
+            bands, smoothing
+        );
+        Limit::Mode(bands, smoothing)
+    }
+
+    /// Creates a new limit, which splits the points into a number of parts with nearly equal size.
+    #[must_use]
+    pub fn parts(measure: Measure, parts: u32) -> Self {
+        debug!("number of parts: {}, measured by {:?}", parts, measure);
+        Limit::Parts(measure, parts)
+    }
+
+    /// Creates a new limit, which splits the points into parts with nearly equal size,
+    /// where each part is below the maximum, e.g. 210 km with a maximum of 50 km become 5 parts of 42 km.
+    #[must_use]
+    pub fn balance(measure: Measure, max: u32) -> Self {
+        debug!(
+            "maximum of balanced parts: {}, measured by {:?}",
+            max, measure
+        );
+        Limit::Balance(measure, max)
+    }
+
+    /// Creates a new limit, which splits the points into a number of parts with an effort as even as possible,
+    /// see [`efforts_along`] for the effort. Given the locations and the distance in meter, e.g. to huts,
+    /// each part ends near one of them, which match the filter. The locations of the sources are merged,
+    /// see [`merge_split_points`].
+    /// There are fewer parts, when there are not enough points near the locations.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error, if locations are given, but none of them matches the filter.
+    pub fn optimal(
+        parts: u32,
+        near: Option<(Vec<Vec<Waypoint>>, u32)>,
+        filter: &Filter,
+    ) -> io::Result<Self> {
+        debug!("number of parts with an even effort: {}", parts);
+        let near = match near {
+            Some((sources, distance)) => {
+                let waypoints = Limit::matching(sources, filter, false)?;
+                debug!(
+                    "parts end within {} m of {} locations",
+                    distance,
+                    waypoints.len()
+                );
+                Some((
+                    Box::new(SplitPoints::new(waypoints, Reuse::Always)),
+                    distance,
+                ))
+            }
+            None => None,
+        };
+        Ok(Limit::Optimal(parts, near))
+    }
+
+    /// Creates a new limit, which is exceeded as soon as one of the given limits is exceeded.
+    #[must_use]
+    pub fn any(limits: Vec<Limit>) -> Self {
+        debug!(
+            "number of limits where any can be exceeded: {}",
+            limits.len()
+        );
+        Limit::Any(limits)
+    }
+
+    /// Creates a new limit, which is exceeded only when all of the given limits are exceeded.
+    #[must_use]
+    pub fn all(limits: Vec<Limit>) -> Self {
+        debug!(
+            "number of limits where all must be exceeded: {}",
+            limits.len()
+        );
+        Limit::All(limits)
     }
 
     /// If the points exceed a defined limit, (see enum values of [Limit])
-    /// this method returns true, else false.
-    pub fn exceeds(&mut self, points: &mut [Waypoint]) -> bool {
+    /// this method returns the position where the points should be split, else None.
+    pub fn exceeds(&mut self, points: &mut Vec<Waypoint>) -> Option<Split> {
+        self.exceeds_after(Carried::default(), points)
+    }
+
+    /// Like [Limit::exceeds] for the points of a chunk, which start at the point of the previous split.
+    /// The carried points are only counted by the limits of a file, e.g. its size.
+    pub fn exceeds_after(&mut self, carried: Carried, points: &mut Vec<Waypoint>) -> Option<Split> {
+        let split = self.check(carried, points)?;
+        //the points of the next chunk are different ones, so the state of the current chunk is built again
+        self.restart(points, &split);
+        Some(split)
+    }
+
+    // Resets the state, which is built from the points of the current chunk, when the points are split.
+    fn restart(&mut self, points: &[Waypoint], split: &Split) {
         match self {
-            Limit::Points(max_points) => points.len() >= *max_points as usize,
+            Limit::Dwell(_, _, stop) => stop.anchor.reset(),
+            Limit::Loop(_, trail) => trail.reset(),
+            Limit::Size(budget) => budget.total.reset(),
+            Limit::Duration(_, untimed) => *untimed = 0,
+            Limit::Daylight(pace, Some(schedule), _) => {
+                let (Split::At(index) | Split::Before(index) | Split::Skip(_, index)) = *split;
+                schedule.restart(*pace, points, index);
+            }
+            Limit::Any(limits) | Limit::All(limits) => {
+                limits
+                    .iter_mut()
+                    .for_each(|limit| limit.restart(points, split));
+            }
+            _ => (),
+        }
+    }
+
+    // Checks the points like [Limit::exceeds], the limits of a composite limit are checked by it.
+    fn check(&mut self, carried: Carried, points: &mut Vec<Waypoint>) -> Option<Split> {
+        let last = points.len().checked_sub(1)?;
+        let exceeded = match self {
+            //these are planned in advance, see [Limit::plan]
+            Limit::Parts(..) | Limit::Balance(..) | Limit::Optimal(..) => false,
+            Limit::Any(limits) => return Limit::exceeds_any(limits, carried, points),
+            Limit::All(limits) => return Limit::exceeds_all(limits, carried, points),
+            Limit::Dwell(radius, min_duration, ref mut stop) => {
+                return Limit::exceeds_dwell(*radius, *min_duration, stop, points)
+            }
+            Limit::StrictLength(max_length) => {
+                return Limit::exceeds_strict_length(*max_length, points)
+            }
+            Limit::Loop(dist, ref mut trail) => return Limit::exceeds_loop(*dist, trail, points),
+            Limit::Stage(ref mut stages) => return Limit::exceeds_stage(stages, points),
+            Limit::Size(ref mut budget) => return Limit::exceeds_size(budget, carried, points),
+            Limit::Gate(gates) => return Limit::exceeds_gate(gates, points),
+            Limit::Fence(areas) => return Limit::exceeds_fence(areas, points),
+            Limit::Mode(bands, smoothing) => return Limit::exceeds_mode(bands, *smoothing, points),
+            #[allow(clippy::cast_possible_wrap)]
+            Limit::Points(max_points) => {
+                points.len() as isize + carried.count() >= *max_points as isize
+            }
             Limit::Length(max_length) => distance_all(points) > f64::from(*max_length),
             Limit::Location(ref mut split_points, dist) => {
                 Limit::exceeds_location(*dist, split_points, points)
             }
+            Limit::Duration(max_duration, ref mut untimed) => {
+                Limit::exceeds_duration(*max_duration, untimed, points)
+            }
+            Limit::Pace(pace, max_duration) => pace.estimate(points) > *max_duration,
+            Limit::Daylight(pace, ref mut schedule, margin) => {
+                return Limit::exceeds_daylight(*pace, schedule, *margin, points)
+            }
+            Limit::Gap(max_gap) => Limit::exceeds_gap(*max_gap, points),
+            Limit::Day(zone) => Limit::exceeds_day(*zone, points),
+            Limit::Elevation(max_ascent, max_descent, noise) => {
+                Limit::exceeds_elevation(*max_ascent, *max_descent, *noise, points)
+            }
+        };
+        if !exceeded {
+            return None;
+        }
+        match self {
+            //the gap or the midnight is not bridged, so the last point is only part of the next chunk
+            Limit::Gap(_) | Limit::Day(_) => Some(Split::Before(last)),
+            _ => Some(Split::At(last)),
+        }
+    }
+
+    /// Some limits need all points in advance, these return the sorted indices of the points where to split.
+    /// The point at an index ends a chunk and is also the first point of the next chunk.
+    /// For all other limits this returns None, they are checked with [Limit::exceeds].
+    #[must_use]
+    pub fn plan(&self, points: &[Waypoint]) -> Option<Vec<usize>> {
+        if let Limit::Optimal(parts, near) = self {
+            let candidates: Vec<usize> = (1..points.len().saturating_sub(1))
+                .filter(|index| {
+                    near.as_ref().is_none_or(|(split_points, dist)| {
+                        split_points.is_near(&points[*index], *dist)
+                    })
+                })
+                .collect();
+            let cuts = Limit::optimal_cuts(&efforts_along(points), &candidates, *parts as usize);
+            debug!(
+                "planned {} cuts out of {} candidates",
+                cuts.len(),
+                candidates.len()
+            );
+            return Some(cuts);
+        }
+        //the size along the points, the number of points is measured in segments between them
+        #[allow(clippy::cast_precision_loss)]
+        let sizes = |measure: &Measure| match measure {
+            Measure::Points => (0..points.len()).map(|i| i as f64).collect(),
+            Measure::Length => distances_along(points),
+        };
+        let (sizes, parts) = match self {
+            Limit::Parts(measure, parts) => (sizes(measure), *parts as usize),
+            Limit::Balance(measure, max) => {
+                let sizes = sizes(measure);
+                let total = sizes.last().copied().unwrap_or_default();
+                let max = match measure {
+                    Measure::Points => max.saturating_sub(1),
+                    Measure::Length => *max,
+                };
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let parts = (total / f64::from(max.max(1))).ceil() as usize;
+                (sizes, parts)
+            }
+            _ => return None,
+        };
+        let cuts = Limit::balanced_cuts(&sizes, parts);
+        debug!("planned {} cuts for {} parts", cuts.len(), parts);
+        Some(cuts)
+    }
+
+    /// Returns a label for the points of a chunk, if the limit provides one.
+    /// For instance the label of a limit by day is the date of the points.
+    #[must_use]
+    pub fn label(&self, points: &[Waypoint]) -> Option<String> {
+        match self {
+            Limit::Day(zone) => points
+                .iter()
+                .find_map(timestamp)
+                .map(|time| zone.date(time).to_string()),
+            Limit::Any(limits) | Limit::All(limits) => {
+                limits.iter().find_map(|limit| limit.label(points))
+            }
+            _ => None,
+        }
+    }
+
+    /// Reserves the bytes of a file for the data around the points, e.g. the metadata, which count against the budget.
+    pub fn reserve(&mut self, bytes: u64) {
+        match self {
+            Limit::Size(budget) => {
+                debug!("reserved bytes for the data around the points: {}", bytes);
+                budget.reserved = bytes;
+            }
+            Limit::Any(limits) | Limit::All(limits) => {
+                limits.iter_mut().for_each(|limit| limit.reserve(bytes));
+            }
+            _ => (),
         }
     }
 
+    /// Returns the maximum size of a file in bytes, if the limit has a budget for it.
+    #[must_use]
+    pub fn budget(&self) -> Option<u64> {
+        match self {
+            Limit::Size(budget) => Some(budget.max),
+            Limit::Any(limits) | Limit::All(limits) => {
+                limits.iter().filter_map(Limit::budget).min()
+            }
+            _ => None,
+        }
+    }
+
+    /// Finishes the splitting after all points were checked, e.g. to warn about locations which were never used.
+    pub fn finish(&self) {
+        match self {
+            Limit::Location(split_points, _) => {
+                for point in split_points.skipped() {
+                    let name = point.name.as_deref().unwrap_or("without name");
+                    warn!(
+                        "skipping location {} at {:?}, the points never came near it in order",
+                        name,
+                        point.point()
+                    );
+                }
+            }
+            Limit::Stage(stages) if !stages.missing().is_empty() => {
+                let missing: Vec<String> =
+                    stages.missing().iter().map(ToString::to_string).collect();
+                warn!(
+                    "no location within {} m for the end of stage {}, these end at a length of {} m",
+                    stages.detour,
+                    missing.join(", "),
+                    stages.window.end()
+                );
+            }
+            Limit::Any(limits) | Limit::All(limits) => limits.iter().for_each(Limit::finish),
+            _ => (),
+        }
+    }
+
+    /// Returns a tag for the points of a chunk, if the limit provides one. The tag is the type of the new track/route.
+    /// For instance the tag of a limit by area is the name of the area, which contains the points.
+    #[must_use]
+    pub fn tag(&self, points: &[Waypoint]) -> Option<String> {
+        match self {
+            Limit::Fence(areas) => {
+                //the points of a chunk are either inside or outside, except for the boundary points at its ends
+                let [first, second, ..] = points else {
+                    return None;
+                };
+                let middle = interpolate(first, second, distance(first, second) / 2.0);
+                areas
+                    .iter()
+                    .find(|area| is_inside(&middle, &area.points))
+                    .and_then(|area| area.name.clone())
+            }
+            Limit::Mode(bands, smoothing) => {
+                //the mode at the start of the points, which is also used to detect a change
+                let end = Limit::leading_end(*smoothing, points).unwrap_or(points.len() - 1);
+                Band::find(bands, &points[..=end]).map(|index| bands[index].mode.clone())
+            }
+            Limit::Any(limits) | Limit::All(limits) => {
+                limits.iter().find_map(|limit| limit.tag(points))
+            }
+            _ => None,
+        }
+    }
+
+    // Finds the indices, where the size along the points is nearest to an equal share of the total size.
+    #[allow(clippy::cast_precision_loss)]
+    fn balanced_cuts(sizes: &[f64], parts: usize) -> Vec<usize> {
+        let Some(total) = sizes.last() else {
+            return vec![];
+        };
+        let last = sizes.len() - 1;
+        let mut cuts = Vec::new();
+        for part in 1..parts {
+            let target = total * part as f64 / parts as f64;
+            let next = sizes.partition_point(|size| *size < target).min(last);
+            //take the point before, when it is nearer to the target
+            let index = if next > 0 && target - sizes[next - 1] < sizes[next] - target {
+                next - 1
+            } else {
+                next
+            };
+            //each part has at least one segment and the last point never starts a new part
+            if index > cuts.last().copied().unwrap_or_default() && index < last {
+                cuts.push(index);
+            }
+        }
+        cuts
+    }
+
+    // Finds the cuts at the candidates, which divide the sizes into the parts with the least sum of their squared sizes.
+    // This is the least variance of the sizes, as their total is fixed. The parts are found by dynamic programming,
+    // where the best previous cut moves forward with the cut, which is found by divide and conquer.
+    fn optimal_cuts(sizes: &[f64], candidates: &[usize], parts: usize) -> Vec<usize> {
+        let Some(last) = sizes.len().checked_sub(1) else {
+            return vec![];
+        };
+        //the positions where a part can start or end, the first and the last point are always among them
+        let positions: Vec<usize> = std::iter::once(0)
+            .chain(
+                candidates
+                    .iter()
+                    .copied()
+                    .filter(|index| 0 < *index && *index < last),
+            )
+            .chain(std::iter::once(last))
+            .collect();
+        let count = positions.len();
+        let cost = |from: usize, to: usize| (sizes[positions[to]] - sizes[positions[from]]).powi(2);
+
+        //the least costs to end a number of parts at each position and the previous positions for them
+        let mut costs: Vec<f64> = (0..count).map(|to| cost(0, to)).collect();
+        //each part has at least one segment
+        costs[0] = f64::INFINITY;
+        let mut previous: Vec<Vec<usize>> = Vec::new();
+        for _ in 1..parts.min(count - 1) {
+            let mut next = vec![f64::INFINITY; count];
+            let mut from = vec![0; count];
+            let mut ranges = vec![(1, count - 1, 0, count - 2)];
+            while let Some((low, high, first, last)) = ranges.pop() {
+                if low > high {
+                    continue;
+                }
+                let to = (low + high) / 2;
+                let mut best = first;
+                for (candidate, previous_cost) in costs
+                    .iter()
+                    .enumerate()
+                    .take(last.min(to - 1) + 1)
+                    .skip(first)
+                {
+                    let total = previous_cost + cost(candidate, to);
+                    if total < next[to] {
+                        next[to] = total;
+                        best = candidate;
+                    }
+                }
+                from[to] = best;
+                ranges.push((low, to - 1, first, best));
+                ranges.push((to + 1, high, best, last));
+            }
+            costs = next;
+            previous.push(from);
+        }
+
+        let mut cuts = Vec::with_capacity(previous.len());
+        let mut position = count - 1;
+        for from in previous.iter().rev() {
+            position = from[position];
+            cuts.push(positions[position]);
+        }
+        cuts.reverse();
+        cuts
+    }
+
+    fn exceeds_any(
+        limits: &mut [Limit],
+        carried: Carried,
+        points: &mut Vec<Waypoint>,
+    ) -> Option<Split> {
+        //every limit checks the points to keep track of them, the first exceeded one splits the points
+        let splits = Limit::check_each(limits, carried, points);
+        let (limit, (split, changed)) = limits
+            .iter()
+            .zip(splits)
+            .find_map(|(limit, split)| Some((limit, split?)))?;
+        info!("split triggered by {}", limit);
+        *points = changed;
+        Some(split)
+    }
+
+    fn exceeds_all(
+        limits: &mut [Limit],
+        carried: Carried,
+        points: &mut Vec<Waypoint>,
+    ) -> Option<Split> {
+        //the limits check copies of themselves, which are only kept when all of them are exceeded,
+        //so a limit does not use up a location without a split
+        let mut trials = limits.to_vec();
+        let splits = Limit::check_each(&mut trials, carried, points);
+        if splits.iter().any(Option::is_none) {
+            //the limits which are not exceeded keep what they learned about the points, e.g. the travelled distance
+            for ((limit, trial), split) in limits.iter_mut().zip(trials).zip(&splits) {
+                if split.is_none() {
+                    *limit = trial;
+                }
+            }
+            return None;
+        }
+        limits.clone_from_slice(&trials);
+        let all: Vec<String> = limits.iter().map(ToString::to_string).collect();
+        info!("split triggered by all of {}", all.join(", "));
+        //the first limit splits the points
+        let (split, changed) = splits.into_iter().next().flatten()?;
+        *points = changed;
+        Some(split)
+    }
+
+    // Checks the unchanged points with each limit and returns the split of each exceeded limit together with
+    // the points changed by it. A limit inserts a point at the split or replaces the last point, which is undone
+    // before the next limit checks the points.
+    fn check_each(
+        limits: &mut [Limit],
+        carried: Carried,
+        points: &mut Vec<Waypoint>,
+    ) -> Vec<Option<(Split, Vec<Waypoint>)>> {
+        let len = points.len();
+        let last = points.last().cloned();
+        limits
+            .iter_mut()
+            .map(|limit| {
+                let split = limit.check(carried, points)?;
+                let changed = points.clone();
+                if points.len() > len {
+                    if let Split::At(index) | Split::Before(index) = split {
+                        points.remove(index);
+                    }
+                }
+                points.truncate(len);
+                if let (Some(point), Some(last)) = (points.last_mut(), &last) {
+                    point.clone_from(last);
+                }
+                Some((split, changed))
+            })
+            .collect()
+    }
+
+    fn exceeds_strict_length(max_length: u32, points: &mut Vec<Waypoint>) -> Option<Split> {
+        let length = distance_all(points);
+        let max_length = f64::from(max_length);
+        if length <= max_length {
+            return None;
+        }
+        let len = points.len();
+        //without a length there is no exact point, so split at the last one
+        if max_length <= 0.0 {
+            return Some(Split::At(len - 1));
+        }
+        let (previous, last) = (&points[len - 2], &points[len - 1]);
+        let remaining = max_length - (length - distance(previous, last));
+        //the length is already reached at the previous point, less than a millimeter is ignored
+        if remaining < 0.001 {
+            return Some(Split::At(len - 2));
+        }
+        let point = interpolate(previous, last, remaining);
+        trace!(
+            "interpolated point after {} meter: {:?}",
+            remaining,
+            point.point()
+        );
+        points.insert(len - 1, point);
+        Some(Split::At(len - 1))
+    }
+
+    fn exceeds_duration(max_duration: Duration, untimed: &mut usize, points: &[Waypoint]) -> bool {
+        //points without a timestamp are skipped, the time is measured between the points which have one
+        if let Some(last) = points.last() {
+            if last.time.is_none() {
+                //only the first one of a chunk is reported, e.g. a track without any timestamps is never split
+                if *untimed == 0 {
+                    warn!(
+                        "skipping points without timestamp, starting at {:?}",
+                        last.point()
+                    );
+                }
+                *untimed += 1;
+                return false;
+            }
+        }
+        elapsed(points) >= max_duration
+    }
+
+    fn exceeds_elevation(
+        max_ascent: u32,
+        max_descent: Option<u32>,
+        noise: u32,
+        points: &[Waypoint],
+    ) -> bool {
+        let (ascent, descent) = elevation_change(points, f64::from(noise));
+        ascent > f64::from(max_ascent) || max_descent.is_some_and(|max| descent > f64::from(max))
+    }
+
+    fn exceeds_dwell(
+        radius: u32,
+        min_duration: Duration,
+        stop: &mut Stop,
+        points: &[Waypoint],
+    ) -> Option<Split> {
+        let radius = f64::from(radius);
+        let len = points.len();
+        if len < 2 {
+            return None;
+        }
+        let anchor = stop
+            .anchor
+            .sync(points, |points| Stop::anchor(points, radius));
+        //nothing to do as long as we did not leave the anchor of the stop
+        if distance(&points[*anchor], &points[len - 1]) <= radius {
+            return None;
+        }
+        let start = mem::replace(anchor, len - 1);
+        if elapsed(&points[start..len - 1]) < min_duration {
+            return None;
+        }
+        debug!("found stop with {} points", len - 1 - start);
+        //the points of the stop are neither part of the current chunk nor of the next one
+        Some(Split::Skip(start, len - 1))
+    }
+
+    fn exceeds_gate(gates: &[Route], points: &mut Vec<Waypoint>) -> Option<Split> {
+        let len = points.len();
+        if len < 2 {
+            return None;
+        }
+        let segment = (&points[len - 2], &points[len - 1]);
+        //when the segment crosses more than one gate, the first one crossed ends the chunk
+        let (gate, mut point) = gates
+            .iter()
+            .filter_map(|gate| {
+                let point = crossing((&gate.points[0], &gate.points[1]), segment)?;
+                Some((gate, point))
+            })
+            .min_by(|(_, p1), (_, p2)| {
+                distance(segment.0, p1).total_cmp(&distance(segment.0, p2))
+            })?;
+        debug!("crossing gate {:?} at {:?}", gate.name, point.point());
+        point.name.clone_from(&gate.name);
+        points.insert(len - 1, point);
+        Some(Split::At(len - 1))
+    }
+
+    fn exceeds_fence(areas: &[Route], points: &mut Vec<Waypoint>) -> Option<Split> {
+        let len = points.len();
+        if len < 2 {
+            return None;
+        }
+        let segment = (&points[len - 2], &points[len - 1]);
+        //the boundary of an area is closed by the edge from the last to the first point
+        let point = areas
+            .iter()
+            .flat_map(|area| area.points.iter().zip(area.points.iter().cycle().skip(1)))
+            .filter_map(|edge| crossing(edge, segment))
+            .min_by(|p1, p2| distance(segment.0, p1).total_cmp(&distance(segment.0, p2)))?;
+        debug!("crossing boundary of an area at {:?}", point.point());
+        points.insert(len - 1, point);
+        Some(Split::At(len - 1))
+    }
+
+    fn exceeds_mode(bands: &[Band], smoothing: Duration, points: &[Waypoint]) -> Option<Split> {
+        //the mode at the start is compared with the mode at the end, both are averaged over the smoothing duration
+        let end = Limit::leading_end(smoothing, points)?;
+        let start = (0..points.len())
+            .rev()
+            .find(|index| elapsed(&points[*index..]) >= smoothing)?;
+        if start < end {
+            return None;
+        }
+        let current = Band::find(bands, &points[..=end])?;
+        let next = Band::find(bands, &points[start..])?;
+        if current == next {
+            return None;
+        }
+        debug!(
+            "mode changes from {} to {}",
+            bands[current].mode, bands[next].mode
+        );
+        //the next chunk starts with the points, where the speed is in the new band
+        Some(Split::At(start))
+    }
+
+    // Returns the index of the first point, where the time elapsed since the start is at least the smoothing.
+    fn leading_end(smoothing: Duration, points: &[Waypoint]) -> Option<usize> {
+        (0..points.len()).find(|index| elapsed(&points[..=*index]) >= smoothing)
+    }
+
+    fn exceeds_loop(
+        dist: u32,
+        trail: &mut Tally<Trail>,
+        points: &mut Vec<Waypoint>,
+    ) -> Option<Split> {
+        if points.is_empty() {
+            return None;
+        }
+        let trail = trail.sync(points, Trail::new);
+        let split = Limit::closes_loop(dist, trail, points);
+        if split.is_none() {
+            trail.push(points);
+        }
+        split
+    }
+
+    // Checks the last segment against the earlier points and segments in the index.
+    fn closes_loop(dist: u32, trail: &Trail, points: &mut Vec<Waypoint>) -> Option<Split> {
+        let len = points.len();
+        if len < 3 {
+            return None;
+        }
+        let last = &points[len - 1];
+        //the last segment can only cross one of the earlier segments, which are not adjacent
+        let segment = (&points[len - 2], last);
+        let envelope =
+            AABB::from_corners(segment.0.point().x_y().into(), last.point().x_y().into());
+        let mut earlier: Vec<usize> = trail
+            .segments
+            .locate_in_envelope_intersecting(&envelope)
+            .map(|segment| segment.data)
+            .filter(|index| index + 3 < len)
+            .collect();
+        earlier.sort_unstable();
+        if let Some(point) = earlier
+            .into_iter()
+            .find_map(|index| crossing((&points[index], &points[index + 1]), segment))
+        {
+            debug!("loop closed by crossing at {:?}", point.point());
+            points.insert(len - 1, point);
+            return Some(Split::At(len - 1));
+        }
+        //the loop is closed by a near point, when the points were farther away after it
+        let bounds = padded_bounds((last, last), f64::from(dist));
+        let envelope = AABB::from_corners(bounds.min().x_y().into(), bounds.max().x_y().into());
+        let near: Vec<usize> = trail
+            .points
+            .locate_in_envelope(&envelope)
+            .map(|point| point.data)
+            .filter(|index| *index < len - 1 && distance(&points[*index], last) <= f64::from(dist))
+            .collect();
+        let first = near.iter().min()?;
+        //all points after the first near one are near as well, when none of them is farther away
+        if near.len() < len - 1 - first {
+            debug!("loop closed near {:?}", points[*first].point());
+            return Some(Split::At(len - 1));
+        }
+        None
+    }
+
+    fn exceeds_day(zone: Zone, points: &[Waypoint]) -> bool {
+        //points without a timestamp are skipped, the last one with a timestamp marks the previous day
+        match points.split_last() {
+            Some((last, previous)) => {
+                match (timestamp(last), previous.iter().rev().find_map(timestamp)) {
+                    (Some(last), Some(previous)) => zone.date(last) != zone.date(previous),
+                    _ => false,
+                }
+            }
+            None => false,
+        }
+    }
+
+    fn exceeds_gap(max_gap: Duration, points: &[Waypoint]) -> bool {
+        //points without a timestamp are skipped, the gap is measured from the last one with a timestamp
+        match points.split_last() {
+            Some((last, previous)) => {
+                match (timestamp(last), previous.iter().rev().find_map(timestamp)) {
+                    (Some(last), Some(previous)) => last - previous > max_gap,
+                    _ => false,
+                }
+            }
+            None => false,
+        }
+    }
+
+    fn exceeds_size(budget: &mut Budget, carried: Carried, points: &[Waypoint]) -> Option<Split> {
+        let last = points.len().checked_sub(1)?;
+        let base = budget.base;
+        let skip = usize::from(!carried.shared);
+        //the carried points are written into the file, but the first point is not, when it ends the previous chunk
+        let total = budget.total.sync(points, |points| {
+            carried
+                .points
+                .iter()
+                .chain(points.iter().skip(skip))
+                .map(|point| Budget::point_size(base, point))
+                .sum()
+        });
+        if last >= skip {
+            *total += Budget::point_size(base, &points[last]);
+        }
+        if budget.reserved + *total <= budget.max {
+            return None;
+        }
+        //a chunk has at least one segment, even when it is too large, which is detected when the file is written
+        if last < 2 {
+            return None;
+        }
+        //the chunk ends before the last point, which would be too much
+        Some(Split::At(last - 1))
+    }
+
+    fn exceeds_stage(stages: &mut Stages, points: &mut Vec<Waypoint>) -> Option<Split> {
+        if distance_all(points) <= f64::from(*stages.window.end()) {
+            return None;
+        }
+        stages.count += 1;
+        match stages.best(points) {
+            Some((index, point)) => {
+                debug!("stage {} ends at {:?}", stages.count, point.name);
+                //the interception point can be one of the points, then it is not inserted again
+                if index > 0 && distance(&points[index], &point) < 1e-3 {
+                    return Some(Split::At(index));
+                }
+                if distance(&point, &points[index + 1]) >= 1e-3 {
+                    points.insert(index + 1, point);
+                }
+                Some(Split::At(index + 1))
+            }
+            None => {
+                stages.missing.push(stages.count);
+                let along = distances_along(points);
+                let max = f64::from(*stages.window.end());
+                let index = along
+                    .partition_point(|length| *length <= max)
+                    .saturating_sub(1);
+                Some(Split::At(index.max(1)))
+            }
+        }
+    }
+
+    fn exceeds_daylight(
+        pace: Pace,
+        schedule: &mut Option<Schedule>,
+        margin: Duration,
+        points: &[Waypoint],
+    ) -> Option<Split> {
+        let last = points.len().checked_sub(1).filter(|last| *last > 0)?;
+        let Schedule {
+            morning,
+            start: day,
+            ..
+        } = (*schedule)?;
+        //each segment would be a chunk, when the day ends before it starts
+        if last == 1 && day == morning {
+            let first = &points[0];
+            if let Some(sunset) = sunset(first, solar_date(day, first.point().x())) {
+                if day + margin >= sunset {
+                    warn!(
+                        "not splitting by daylight, the start at {} is too late for the sunset at {} with a margin of {:?}",
+                        day, sunset, margin
+                    );
+                    *schedule = None;
+                    return None;
+                }
+            }
+        }
+        let point = &points[last];
+        let sunset = sunset(point, solar_date(day, point.point().x()))?;
+        let arrival = day + pace.estimate(points);
+        if arrival + margin <= sunset {
+            return None;
+        }
+        debug!(
+            "arrival at {} is too late for the sunset at {}",
+            arrival, sunset
+        );
+        if let Some(schedule) = schedule {
+            schedule.night = true;
+        }
+        //the previous point is the last one reached in time, but a chunk has at least one segment
+        Some(Split::At((last - 1).max(1)))
+    }
+
     fn exceeds_location(
         dist: u32,
-        split_points: &mut Vec<Waypoint>,
+        split_points: &mut SplitPoints,
         points: &mut [Waypoint],
     ) -> bool {
         let len = points.len();
@@ -71,19 +1720,23 @@ impl Limit {
         }
 
         let segment = (&points[len - 2], &points[len - 1]);
-        let mut map = Limit::interception_points(dist, split_points, segment);
+        split_points.travel(segment);
+        let candidates = split_points.candidates(segment, dist);
+        let map = Limit::interception_points(&split_points.points, &candidates, segment);
+        let near: Vec<usize> = map.keys().map(|(_, index)| *index).collect();
+        split_points.leave(&near);
 
         //replace last point with the interception point, that has the shortest distance
-        match map.pop_first() {
-            Some(entry) => {
-                debug!("shortest distance in milimeter: {}", entry.0);
-                //Each split point can be used only once. When we add an interception point,
-                //we will also remove the splitting point. The consequences of keeping the splitting point
+        let usable = map
+            .into_iter()
+            .find(|((_, index), _)| split_points.is_usable(*index, dist));
+        match usable {
+            Some(((dist, index), point)) => {
+                debug!("shortest distance in milimeter: {}", dist);
+                //A split point is not used again during the same pass. The consequences of using it
                 //would be weird tracks, containing just a few points till the next interception point.
-                let index = entry.1 .0;
-                split_points.remove(index);
+                split_points.use_point(index, &point);
                 //finally the interception point
-                let point = entry.1 .1;
                 points[len - 1] = point;
                 true
             }
@@ -91,26 +1744,25 @@ impl Limit {
         }
     }
 
-    // This creates a map of distances and interception points from each split point to the segment.
+    // This creates a map of distances and interception points from each candidate of the split points to the segment.
     // If the distance is above the min_dist, the interception point is not considered.
     // The map is sorted, where the first entry is the shortest distance with the corresponding interception point.
+    // The index of the split point is part of the key, so split points with the same distance are all kept.
     // The unit of the distance is milimeter.
     #[allow(clippy::cast_possible_truncation)]
     fn interception_points(
-        dist: u32,
-        split_points: &mut [Waypoint],
+        split_points: &[Waypoint],
+        candidates: &[(usize, u32)],
         segment: (&Waypoint, &Waypoint),
-    ) -> BTreeMap<i64, (usize, Waypoint)> {
-        let min_dist = f64::from(dist);
-
-        split_points
+    ) -> BTreeMap<(i64, usize), Waypoint> {
+        candidates
             .iter()
-            .enumerate()
-            .filter_map(|(index, split_point)| {
+            .map(|(index, dist)| (*index, &split_points[*index], f64::from(*dist)))
+            .filter_map(|(index, split_point, min_dist)| {
                 let mut ip = intercept(split_point, segment);
                 // The interception point can be far off from the segment.
                 // So we consider only those which are within the distance.
-                if !is_near_segment(&ip, segment, f64::from(dist)) {
+                if !is_near_segment(&ip, segment, min_dist) {
                     return None;
                 }
                 let dist = distance(split_point, &ip);
@@ -123,7 +1775,7 @@ impl Limit {
                     if let Some(name) = &split_point.name {
                         ip.name = Some(format!("nearby {name}").to_string());
                     }
-                    Some((dist, (index, ip)))
+                    Some(((dist, index), ip))
                 } else {
                     None
                 }
@@ -132,10 +1784,85 @@ impl Limit {
     }
 }
 
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |limits: &[Limit]| {
+            limits
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            Limit::Points(max_points) => write!(f, "{max_points} points"),
+            Limit::Length(max_length) => write!(f, "length of {max_length} m"),
+            Limit::StrictLength(max_length) => write!(f, "exact length of {max_length} m"),
+            Limit::Location(_, dist) => write!(f, "location within {dist} m"),
+            Limit::Stage(stages) => write!(
+                f,
+                "stage between {} m and {} m ending within {} m of a location",
+                stages.window.start(),
+                stages.window.end(),
+                stages.detour
+            ),
+            Limit::Duration(max_duration, _) => {
+                write!(f, "duration of {}", format_duration(*max_duration))
+            }
+            Limit::Pace(pace, max_duration) => write!(
+                f,
+                "estimated time of {} by {pace}",
+                format_duration(*max_duration)
+            ),
+            Limit::Daylight(pace, _, margin) => write!(
+                f,
+                "daylight by {pace} with a margin of {}",
+                format_duration(*margin)
+            ),
+            Limit::Gap(max_gap) => write!(f, "time gap of {}", format_duration(*max_gap)),
+            Limit::Day(zone) => write!(f, "day in {zone}"),
+            Limit::Loop(dist, _) => write!(f, "loop within {dist} m"),
+            Limit::Size(budget) => write!(f, "file size of {} bytes", budget.max),
+            Limit::Gate(gates) => write!(f, "crossing one of {} gates", gates.len()),
+            Limit::Fence(areas) => write!(f, "boundary of one of {} areas", areas.len()),
+            Limit::Mode(bands, smoothing) => write!(
+                f,
+                "mode of {} speed bands averaged over {}",
+                bands.len(),
+                format_duration(*smoothing)
+            ),
+            Limit::Parts(measure, parts) => write!(f, "{parts} parts by {measure:?}"),
+            Limit::Balance(measure, max) => write!(f, "balanced parts up to {max} by {measure:?}"),
+            Limit::Optimal(parts, near) => match near {
+                Some((split_points, dist)) => write!(
+                    f,
+                    "{parts} parts with an even effort ending within {dist} m of {} locations",
+                    split_points.len()
+                ),
+                None => write!(f, "{parts} parts with an even effort"),
+            },
+            Limit::Dwell(radius, min_duration, _) => write!(
+                f,
+                "stop within {radius} m for {}",
+                format_duration(*min_duration)
+            ),
+            Limit::Elevation(max_ascent, max_descent, _) => match max_descent {
+                Some(max_descent) => {
+                    write!(f, "ascent of {max_ascent} m or descent of {max_descent} m")
+                }
+                None => write!(f, "ascent of {max_ascent} m"),
+            },
+            Limit::Any(limits) => write!(f, "any of ({})", join(limits)),
+            Limit::All(limits) => write!(f, "all of ({})", join(limits)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use approx_eq::assert_approx_eq;
     use geo_types::Point;
-    use gpx::Waypoint;
+    use gpx::{Route, Waypoint};
+    use time::macros::datetime;
 
     use super::*;
 
@@ -145,7 +1872,7 @@ mod tests {
 
     #[test]
     fn location() {
-        let lim = Limit::location("target/debug/pois.gpx", 10);
+        let lim = Limit::location("target/debug/pois.gpx", 10, &Filter::default()).unwrap();
         match lim {
             Limit::Location(waypoints, dist) => {
                 assert_eq!(2, waypoints.len());
@@ -156,41 +1883,1120 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(
-        expected = "can't read file with splitting points: Os { code: 2, kind: NotFound, message: \"No such file or directory\" }"
-    )]
     fn wrong_location() {
-        let _ = Limit::location("pois.gpx", 10);
+        let Err(err) = Limit::location("pois.gpx", 10, &Filter::default()) else {
+            panic!("unexpected result");
+        };
+        assert_eq!(ErrorKind::NotFound, err.kind());
+        assert!(err
+            .to_string()
+            .starts_with("can't read file with splitting points pois.gpx: "));
+    }
+
+    #[test]
+    fn malformed_location() {
+        let Err(err) = Limit::location("target/debug/malformed.gpx", 10, &Filter::default()) else {
+            panic!("unexpected result");
+        };
+        assert_eq!(ErrorKind::InvalidData, err.kind());
+    }
+
+    #[test]
+    fn empty_location() {
+        let Err(err) = Limit::locations(vec![], 2, Reuse::Once, &Filter::default()) else {
+            panic!("unexpected result");
+        };
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+        let filter = Filter {
+            symbol: Some("Campground".to_string()),
+            ..Filter::default()
+        };
+        let Err(err) = Limit::location("target/debug/pois.gpx", 10, &filter) else {
+            panic!("unexpected result");
+        };
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
     }
 
     #[test]
     fn exceeds_location_false() {
-        let mut lim = Limit::Location(Box::default(), 2);
-        assert!(!lim.exceeds(&mut [Waypoint::default()]));
-        let mut lim = Limit::Location(Box::new(vec![Waypoint::default()]), 2);
-        assert!(!lim.exceeds(&mut [Waypoint::default()]));
+        let mut lim = Limit::locations(
+            vec![vec![Waypoint::default()]],
+            2,
+            Reuse::Once,
+            &Filter::default(),
+        )
+        .unwrap();
+        assert_eq!(None, lim.exceeds(&mut vec![Waypoint::default()]));
     }
 
     #[test]
     fn exceeds_location_true() {
-        let split_points = Box::new(vec![
+        let split_points = vec![
             waypoint(13.535369, 52.643826),
             waypoint(13.535368, 52.643825),
-        ]);
-        let mut lim = Limit::Location(split_points, 15);
-        let points = &mut [
+        ];
+        let mut lim =
+            Limit::locations(vec![split_points], 15, Reuse::Once, &Filter::default()).unwrap();
+        let points = &mut vec![
             waypoint(13.533826, 52.643605),
             waypoint(13.535629, 52.644021),
         ];
-        assert!(lim.exceeds(points));
+        assert_eq!(Some(Split::At(1)), lim.exceeds(points));
+    }
+
+    //returns the longitudes of the splits, when the points are added one after another
+    fn splits(lim: &mut Limit, points: &[Waypoint]) -> Vec<f64> {
+        let mut chunk = vec![];
+        let mut splits = vec![];
+        for point in points {
+            chunk.push(point.clone());
+            if let Some(Split::At(index)) = lim.exceeds(&mut chunk) {
+                chunk = chunk.split_off(index);
+                splits.push((chunk[0].point().x() * 10000.0).round() / 10000.0);
+            }
+        }
+        splits
+    }
+
+    #[test]
+    fn exceeds_location_reuse() {
+        //out and back, passing the location twice with nearly 900 m in between
+        let points: Vec<Waypoint> = (0..=6)
+            .chain((0..6).rev())
+            .map(|x| waypoint(f64::from(x) * 0.001, 0.0))
+            .collect();
+        let location = || vec![waypoint(0.0025, 0.00005)];
+        let splits = |reuse| {
+            splits(
+                &mut Limit::locations(vec![location()], 10, reuse, &Filter::default()).unwrap(),
+                &points,
+            )
+            .len()
+        };
+        assert_eq!(1, splits(Reuse::Once));
+        assert_eq!(2, splits(Reuse::Always));
+        assert_eq!(2, splits(Reuse::After(500)));
+        assert_eq!(1, splits(Reuse::After(2000)));
+    }
+
+    #[test]
+    fn exceeds_location_ordered() {
+        //out and back, where the last two locations are meant for the way back
+        let points: Vec<Waypoint> = (0..=6)
+            .chain((0..6).rev())
+            .map(|x| waypoint(f64::from(x) * 0.001, 0.0))
+            .collect();
+        let locations = vec![
+            waypoint(0.0045, 0.00005),
+            waypoint(0.0025, 0.00005),
+            waypoint(0.0015, 0.00005),
+        ];
+        let mut lim =
+            Limit::locations(vec![locations.clone()], 10, Reuse::Once, &Filter::default()).unwrap();
+        assert_eq!(vec![0.0015, 0.0025, 0.0045], splits(&mut lim, &points));
+        let mut lim =
+            Limit::locations(vec![locations], 10, Reuse::Ordered, &Filter::default()).unwrap();
+        assert_eq!(vec![0.0045, 0.0025, 0.0015], splits(&mut lim, &points));
+        //the same location for both ways
+        let locations = vec![waypoint(0.0025, 0.00005), waypoint(0.0025, 0.00005)];
+        let mut lim =
+            Limit::locations(vec![locations], 10, Reuse::Ordered, &Filter::default()).unwrap();
+        assert_eq!(vec![0.0025, 0.0025], splits(&mut lim, &points));
+        match lim {
+            Limit::Location(split_points, _) => assert!(split_points.skipped().is_empty()),
+            _ => panic!("unexpected result"),
+        }
+    }
+
+    #[test]
+    fn filter_locations() {
+        let mut camp = waypoint(0.0, 0.0);
+        camp.symbol = Some("Campground".to_string());
+        camp.name = Some("Camp Lakeside".to_string());
+        let mut hut = waypoint(0.0, 0.0);
+        hut.type_ = Some("Hut".to_string());
+        hut.name = Some("Hut Edelweiss".to_string());
+        let filter = |symbol: Option<&str>, kind: Option<&str>, name: Option<&str>| Filter {
+            symbol: symbol.map(str::to_string),
+            kind: kind.map(str::to_string),
+            name: name.map(|name| Regex::new(name).unwrap()),
+        };
+
+        assert!(Filter::default().matches(&camp));
+        assert!(filter(Some("campground"), None, None).matches(&camp));
+        assert!(!filter(Some("campground"), None, None).matches(&hut));
+        assert!(filter(None, Some("hut"), None).matches(&hut));
+        assert!(!filter(None, Some("hut"), None).matches(&camp));
+        assert!(filter(None, None, Some("^(Hut|Camp) ")).matches(&camp));
+        assert!(!filter(None, None, Some("^Hut")).matches(&camp));
+        assert!(!filter(Some("Campground"), None, Some("^Hut")).matches(&camp));
+        assert!(!filter(None, None, Some(".*")).matches(&waypoint(0.0, 0.0)));
+    }
+
+    #[test]
+    fn exceeds_location_radius() {
+        let points: Vec<Waypoint> = (0..=6)
+            .map(|x| waypoint(f64::from(x) * 0.001, 0.0))
+            .collect();
+        //a lake about 550 m away from the track and a location, which is near
+        let mut lake = waypoint(0.0025, 0.005);
+        lake.comment = Some("large lake, radius=600 m".to_string());
+        let locations = vec![lake, waypoint(0.0045, 0.00005)];
+        let mut lim =
+            Limit::locations(vec![locations.clone()], 10, Reuse::Once, &Filter::default()).unwrap();
+        assert_eq!(vec![0.0025, 0.0045], splits(&mut lim, &points));
+        //the radius overrides a larger distance as well
+        let mut lim =
+            Limit::locations(vec![locations], 1000, Reuse::Once, &Filter::default()).unwrap();
+        assert_eq!(2, splits(&mut lim, &points).len());
+        let mut lim = Limit::locations(
+            vec![vec![waypoint(0.0025, 0.005)]],
+            10,
+            Reuse::Once,
+            &Filter::default(),
+        )
+        .unwrap();
+        assert!(splits(&mut lim, &points).is_empty());
+    }
+
+    #[test]
+    fn exceeds_stage() {
+        let points: Vec<Waypoint> = (0..=30)
+            .map(|x| waypoint(f64::from(x) * 0.001, 0.0))
+            .collect();
+        //the location on the track is before the window, the nearer one inside of it ends the stage
+        let locations = vec![
+            waypoint(0.005, 0.0),
+            waypoint(0.010, 0.0003),
+            waypoint(0.012, 0.0001),
+        ];
+        let mut lim = Limit::stages(vec![locations], 1000..=1500, 100, &Filter::default()).unwrap();
+        assert_eq!(vec![0.012, 0.025], splits(&mut lim, &points));
+        match lim {
+            Limit::Stage(stages) => assert_eq!(vec![2], stages.missing()),
+            _ => panic!("unexpected result"),
+        }
+    }
+
+    #[test]
+    fn radius_of_location() {
+        let mut point = waypoint(0.0, 0.0);
+        assert_eq!(None, SplitPoints::radius_of(&point));
+        point.description = Some("radius=250".to_string());
+        assert_eq!(Some(250), SplitPoints::radius_of(&point));
+        point.comment = Some("a lake with radius=500m".to_string());
+        assert_eq!(Some(500), SplitPoints::radius_of(&point));
+        point.comment = Some("radius=wide".to_string());
+        assert_eq!(Some(250), SplitPoints::radius_of(&point));
+    }
+
+    #[test]
+    fn skipped_locations() {
+        let points: Vec<Waypoint> = (0..=6)
+            .map(|x| waypoint(f64::from(x) * 0.001, 0.0))
+            .collect();
+        //the second location is never reached, so the third one is skipped too
+        let locations = vec![
+            waypoint(0.0015, 0.00005),
+            waypoint(0.0015, 0.01),
+            waypoint(0.0045, 0.00005),
+        ];
+        let mut lim =
+            Limit::locations(vec![locations], 10, Reuse::Ordered, &Filter::default()).unwrap();
+        assert_eq!(vec![0.0015], splits(&mut lim, &points));
+        match lim {
+            Limit::Location(split_points, _) => assert_eq!(2, split_points.skipped().len()),
+            _ => panic!("unexpected result"),
+        }
+    }
+
+    #[test]
+    fn parse_reuse() {
+        assert_eq!(Ok(Reuse::Ordered), "ordered".parse());
+        assert_eq!(Ok(Reuse::Once), "once".parse());
+        assert_eq!(Ok(Reuse::Always), "always".parse());
+        assert_eq!(Ok(Reuse::After(500)), "500".parse());
+        assert!("twice".parse::<Reuse>().is_err());
+    }
+
+    #[test]
+    fn exceeds_strict_length_false() {
+        let mut lim = Limit::strict_length(1000);
+        let points = &mut vec![waypoint(0.0, 0.0), waypoint(0.008, 0.0)];
+        assert_eq!(None, lim.exceeds(points));
+        assert_eq!(2, points.len());
+    }
+
+    #[test]
+    fn exceeds_strict_length_true() {
+        let mut lim = Limit::strict_length(1000);
+        //roughly 445 m between the points
+        let points = &mut vec![
+            waypoint(0.0, 0.0),
+            waypoint(0.004, 0.0),
+            waypoint(0.008, 0.0),
+            waypoint(0.012, 0.0),
+        ];
+        assert_eq!(Some(Split::At(3)), lim.exceeds(points));
+        assert_eq!(5, points.len());
+        assert_approx_eq!(1000.0, distance_all(&points[..4]), 1e-6);
+        assert_eq!(waypoint(0.012, 0.0).point(), points[4].point());
+    }
+
+    #[test]
+    fn exceeds_strict_length_at_previous() {
+        let mut lim = Limit::strict_length(1000);
+        let p1 = waypoint(0.0, 0.0);
+        let p2 = interpolate(&p1, &waypoint(0.01, 0.0), 1000.0);
+        let points = &mut vec![p1, p2, waypoint(0.01, 0.0)];
+        assert_eq!(Some(Split::At(1)), lim.exceeds(points));
+        assert_eq!(3, points.len());
+    }
+
+    fn timed_waypoint(minute: u8) -> Waypoint {
+        let mut point = waypoint(0.0, 0.0);
+        point.time = Some(
+            datetime!(2021-07-15 11:00 UTC)
+                .replace_minute(minute)
+                .unwrap()
+                .into(),
+        );
+        point
+    }
+
+    #[test]
+    fn exceeds_duration_false() {
+        let mut lim = Limit::duration(Duration::from_secs(30 * 60));
+        assert_eq!(None, lim.exceeds(&mut vec![]));
+        assert_eq!(None, lim.exceeds(&mut vec![timed_waypoint(0)]));
+        assert_eq!(
+            None,
+            lim.exceeds(&mut vec![timed_waypoint(0), timed_waypoint(29)])
+        );
+    }
+
+    #[test]
+    fn exceeds_duration_true() {
+        let mut lim = Limit::duration(Duration::from_secs(30 * 60));
+        assert_eq!(
+            Some(Split::At(1)),
+            lim.exceeds(&mut vec![timed_waypoint(0), timed_waypoint(30)])
+        );
+        assert_eq!(
+            Some(Split::At(2)),
+            lim.exceeds(&mut vec![
+                timed_waypoint(0),
+                Waypoint::default(),
+                timed_waypoint(45)
+            ])
+        );
+    }
+
+    #[test]
+    fn exceeds_duration_skips_missing_time() {
+        let mut lim = Limit::duration(Duration::from_secs(30 * 60));
+        assert_eq!(
+            None,
+            lim.exceeds(&mut vec![
+                timed_waypoint(0),
+                timed_waypoint(45),
+                Waypoint::default()
+            ])
+        );
+        //a track without timestamps is not split, the skipped points are counted to warn only once per chunk
+        let points = vec![Waypoint::default(); 5];
+        assert!(splits(&mut lim, &points).is_empty());
+        assert!(matches!(lim, Limit::Duration(_, 6)));
+        let points = &mut vec![Waypoint::default(), timed_waypoint(0), timed_waypoint(30)];
+        assert_eq!(Some(Split::At(2)), lim.exceeds(points));
+        assert!(matches!(lim, Limit::Duration(_, 0)));
+    }
+
+    #[test]
+    fn exceeds_pace() {
+        //about 1.1 km between the points, which take about 13 minutes by Naismith's rule
+        let points: Vec<Waypoint> = (0..=6)
+            .map(|x| waypoint(f64::from(x) * 0.01, 0.0))
+            .collect();
+        let mut lim = Limit::pace(Pace::Naismith, Duration::from_secs(30 * 60));
+        assert_eq!(vec![0.03, 0.06], splits(&mut lim, &points));
+        let mut lim = Limit::pace(Pace::Flat(20.0), Duration::from_secs(30 * 60));
+        assert!(splits(&mut lim, &points).is_empty());
+        //the climb of 200 m takes 20 minutes more
+        let mut points = points;
+        points[1].elevation = Some(0.0);
+        points[2].elevation = Some(200.0);
+        let mut lim = Limit::pace(Pace::Naismith, Duration::from_secs(30 * 60));
+        assert_eq!(vec![0.02, 0.05], splits(&mut lim, &points));
+    }
+
+    #[test]
+    fn exceeds_daylight() {
+        //eastwards from Berlin on the winter solstice, where the sun sets at about 14:55 UTC
+        let points: Vec<Waypoint> = (0..=30)
+            .map(|x| waypoint(13.40 + f64::from(x) * 0.02, 52.52))
+            .collect();
+        let start = datetime!(2024-12-21 12:00 UTC);
+        let margin = Duration::from_secs(30 * 60);
+        //each of the segments takes about 16 minutes, so 8 of them are done until 14:25 UTC
+        let mut lim = Limit::daylight(start, Pace::Flat(5.0), margin);
+        assert_eq!(vec![13.56, 13.72, 13.88], splits(&mut lim, &points));
+        let mut lim = Limit::daylight(start, Pace::Flat(50.0), margin);
+        assert!(splits(&mut lim, &points).is_empty());
+        //too late at the first point, which would end each segment
+        let mut lim = Limit::daylight(datetime!(2024-12-21 16:00 UTC), Pace::Flat(5.0), margin);
+        assert!(splits(&mut lim, &points).is_empty());
+        let mut lim = Limit::daylight(datetime!(2024-12-21 14:40 UTC), Pace::Flat(5.0), margin);
+        assert!(splits(&mut lim, &points).is_empty());
+        //the sun does not set in summer at the north cape
+        let points: Vec<Waypoint> = (0..=30)
+            .map(|x| waypoint(25.78 + f64::from(x) * 0.02, 71.17))
+            .collect();
+        let mut lim = Limit::daylight(datetime!(2024-06-21 12:00 UTC), Pace::Flat(5.0), margin);
+        assert!(splits(&mut lim, &points).is_empty());
+    }
+
+    #[test]
+    fn exceeds_daylight_any() {
+        let points: Vec<Waypoint> = (0..=30)
+            .map(|x| waypoint(13.40 + f64::from(x) * 0.02, 52.52))
+            .collect();
+        let daylight = Limit::daylight(
+            datetime!(2024-12-21 12:00 UTC),
+            Pace::Flat(5.0),
+            Duration::from_secs(30 * 60),
+        );
+        //the chunk after a split by the length starts at about 13:37 UTC and not in the morning,
+        //so each day ends after two more segments
+        let mut lim = Limit::any(vec![daylight, Limit::length(8000)]);
+        assert_eq!(
+            vec![13.52, 13.56, 13.68, 13.72, 13.84, 13.88, 14.0],
+            splits(&mut lim, &points)
+        );
+    }
+
+    #[test]
+    fn optimal_cuts() {
+        let sizes = [0.0, 1.0, 5.0, 6.0, 7.0, 8.0, 12.0, 13.0];
+        let all: Vec<usize> = (0..sizes.len()).collect();
+        assert_eq!(vec![2, 5], Limit::optimal_cuts(&sizes, &all, 3));
+        assert_eq!(vec![3], Limit::optimal_cuts(&sizes, &all, 2));
+        assert!(Limit::optimal_cuts(&sizes, &all, 1).is_empty());
+        //restricted to some candidates and fewer parts, when there are not enough candidates
+        assert_eq!(vec![1, 6], Limit::optimal_cuts(&sizes, &[1, 6], 3));
+        assert_eq!(vec![1, 6], Limit::optimal_cuts(&sizes, &[1, 6], 5));
+        assert_eq!(all[1..7].to_vec(), Limit::optimal_cuts(&sizes, &all, 10));
+        assert!(Limit::optimal_cuts(&[], &[], 3).is_empty());
+    }
+
+    #[test]
+    fn optimal_cuts_like_brute_force() {
+        //random sizes with a linear congruential generator
+        let mut seed: u64 = 7;
+        let mut sizes = vec![0.0];
+        for _ in 0..11 {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            sizes.push(sizes[sizes.len() - 1] + f64::from((seed >> 40) as u32 % 100));
+        }
+        let all: Vec<usize> = (0..sizes.len()).collect();
+        let squares = |cuts: &[usize]| {
+            let mut bounds = vec![0];
+            bounds.extend(cuts);
+            bounds.push(sizes.len() - 1);
+            bounds
+                .windows(2)
+                .map(|part| (sizes[part[1]] - sizes[part[0]]).powi(2))
+                .sum::<f64>()
+        };
+        //all combinations of two cuts
+        let brute_force = (1..sizes.len() - 1)
+            .flat_map(|first| (first + 1..sizes.len() - 1).map(move |second| vec![first, second]))
+            .map(|cuts| squares(&cuts))
+            .fold(f64::INFINITY, f64::min);
+        assert_approx_eq!(brute_force, squares(&Limit::optimal_cuts(&sizes, &all, 3)));
+    }
+
+    #[test]
+    fn plan_optimal() {
+        //a climb of 100 m in the second segment counts like 1 km
+        let mut points: Vec<Waypoint> = (0..=8)
+            .map(|x| waypoint(f64::from(x) * 0.01, 0.0))
+            .collect();
+        points[1].elevation = Some(0.0);
+        points[2].elevation = Some(100.0);
+        let filter = Filter::default();
+        let lim = Limit::optimal(3, None, &filter).unwrap();
+        assert_eq!(Some(vec![2, 5]), lim.plan(&points));
+        let huts = vec![waypoint(0.04, 0.001), waypoint(0.07, 0.0)];
+        let lim = Limit::optimal(3, Some((vec![huts.clone()], 200)), &filter).unwrap();
+        assert_eq!(Some(vec![4, 7]), lim.plan(&points));
+        //no part ends anywhere, when no hut matches
+        let filter = Filter {
+            name: Some(Regex::new("Camp").unwrap()),
+            ..Default::default()
+        };
+        let Err(err) = Limit::optimal(3, Some((vec![huts], 200)), &filter) else {
+            panic!("unexpected result");
+        };
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+    }
+
+    #[test]
+    fn exceeds_size() {
+        //the coordinates have the same number of digits, so each point has the same size
+        let points: Vec<Waypoint> = (10..=20).map(|x| waypoint(f64::from(x), 0.0)).collect();
+        let budget = Budget::new(0);
+        let size = Budget::point_size(budget.base, &points[1]);
+        assert!(size > 30);
+        //the budget for four points and the reserved bytes
+        let mut lim = Limit::size(4 * size + 100);
+        lim.reserve(100);
+        assert_eq!(Some(4 * size + 100), lim.budget());
+        assert_eq!(vec![13.0, 16.0, 19.0], splits(&mut lim, &points));
+        //a point with a name is about twice as large
+        let mut named = points.clone();
+        named[2].name = Some("Hut".to_string());
+        assert!(Budget::point_size(budget.base, &named[2]) > 2 * size);
+        let mut lim = Limit::size(5 * size);
+        assert_eq!(vec![12.0, 14.0, 18.0], splits(&mut lim, &named));
+        assert_eq!(None, Limit::length(100).budget());
+    }
+
+    #[test]
+    fn exceeds_gap_false() {
+        let mut lim = Limit::gap(Duration::from_secs(10 * 60));
+        assert_eq!(None, lim.exceeds(&mut vec![timed_waypoint(0)]));
+        assert_eq!(
+            None,
+            lim.exceeds(&mut vec![
+                timed_waypoint(0),
+                timed_waypoint(5),
+                timed_waypoint(10)
+            ])
+        );
+        //the point without a timestamp does not bridge the gap
+        assert_eq!(
+            Some(Split::Before(2)),
+            lim.exceeds(&mut vec![
+                timed_waypoint(0),
+                Waypoint::default(),
+                timed_waypoint(30)
+            ])
+        );
+        assert_eq!(
+            None,
+            lim.exceeds(&mut vec![timed_waypoint(0), Waypoint::default()])
+        );
+    }
+
+    #[test]
+    fn exceeds_gap_true() {
+        let mut lim = Limit::gap(Duration::from_secs(10 * 60));
+        assert_eq!(
+            Some(Split::Before(2)),
+            lim.exceeds(&mut vec![
+                timed_waypoint(0),
+                timed_waypoint(5),
+                timed_waypoint(16)
+            ])
+        );
+    }
+
+    fn elevated_waypoint(elevation: f64) -> Waypoint {
+        let mut point = waypoint(0.0, 0.0);
+        point.elevation = Some(elevation);
+        point
+    }
+
+    #[test]
+    fn exceeds_elevation_false() {
+        let mut lim = Limit::elevation(10, Some(5), 1);
+        let points = &mut vec![
+            elevated_waypoint(100.0),
+            elevated_waypoint(110.0),
+            elevated_waypoint(105.0),
+        ];
+        assert_eq!(None, lim.exceeds(points));
+        let mut lim = Limit::elevation(10, None, 1);
+        let points = &mut vec![elevated_waypoint(100.0), elevated_waypoint(80.0)];
+        assert_eq!(None, lim.exceeds(points));
+    }
+
+    #[test]
+    fn exceeds_elevation_true() {
+        let mut lim = Limit::elevation(10, Some(5), 1);
+        let points = &mut vec![elevated_waypoint(100.0), elevated_waypoint(110.5)];
+        assert_eq!(Some(Split::At(1)), lim.exceeds(points));
+        let points = &mut vec![elevated_waypoint(100.0), elevated_waypoint(94.5)];
+        assert_eq!(Some(Split::At(1)), lim.exceeds(points));
+    }
+
+    #[test]
+    fn exceeds_elevation_noise() {
+        let mut lim = Limit::elevation(2, None, 1);
+        let points = &mut vec![
+            elevated_waypoint(100.0),
+            elevated_waypoint(100.9),
+            elevated_waypoint(100.1),
+            elevated_waypoint(100.9),
+            elevated_waypoint(100.1),
+        ];
+        assert_eq!(None, lim.exceeds(points));
+    }
+
+    #[test]
+    fn exceeds_any() {
+        let mut lim = Limit::any(vec![Limit::points(3), Limit::gap(Duration::from_secs(60))]);
+        assert_eq!(None, lim.exceeds(&mut vec![timed_waypoint(0)]));
+        assert_eq!(
+            Some(Split::Before(1)),
+            lim.exceeds(&mut vec![timed_waypoint(0), timed_waypoint(2)])
+        );
+        assert_eq!(
+            Some(Split::At(2)),
+            lim.exceeds(&mut vec![
+                timed_waypoint(0),
+                timed_waypoint(0),
+                timed_waypoint(0)
+            ])
+        );
+    }
+
+    #[test]
+    fn exceeds_all() {
+        let mut lim = Limit::all(vec![Limit::points(3), Limit::gap(Duration::from_secs(60))]);
+        assert_eq!(
+            None,
+            lim.exceeds(&mut vec![timed_waypoint(0), timed_waypoint(2)])
+        );
+        assert_eq!(
+            None,
+            lim.exceeds(&mut vec![
+                timed_waypoint(0),
+                timed_waypoint(0),
+                timed_waypoint(0)
+            ])
+        );
+        assert_eq!(
+            Some(Split::At(2)),
+            lim.exceeds(&mut vec![
+                timed_waypoint(0),
+                timed_waypoint(0),
+                timed_waypoint(2)
+            ])
+        );
+        assert_eq!(
+            None,
+            Limit::all(vec![]).exceeds(&mut vec![timed_waypoint(0)])
+        );
+    }
+
+    #[test]
+    fn exceeds_any_inserted() {
+        //roughly 111 m between the points
+        let points: Vec<Waypoint> = (0..3)
+            .map(|x| waypoint(f64::from(x) * 0.001, 0.0))
+            .collect();
+        //the split of the first exceeded limit is kept, the points inserted by the others are not
+        let mut lim = Limit::any(vec![Limit::points(3), Limit::strict_length(150)]);
+        let chunk = &mut points.clone();
+        assert_eq!(Some(Split::At(2)), lim.exceeds(chunk));
+        assert_eq!(points, *chunk);
+
+        let mut lim = Limit::any(vec![Limit::strict_length(150), Limit::points(3)]);
+        let chunk = &mut points.clone();
+        assert_eq!(Some(Split::At(2)), lim.exceeds(chunk));
+        assert_eq!(4, chunk.len());
+        assert_approx_eq!(0.00135, chunk[2].point().x(), 1e-2);
+    }
+
+    #[test]
+    fn exceeds_all_inserted() {
+        let points: Vec<Waypoint> = (0..3)
+            .map(|x| waypoint(f64::from(x) * 0.001, 0.0))
+            .collect();
+        //the split of the first limit is at its own points, which are not shifted by an inserted point
+        let mut lim = Limit::all(vec![Limit::points(3), Limit::strict_length(150)]);
+        let chunk = &mut points.clone();
+        assert_eq!(Some(Split::At(2)), lim.exceeds(chunk));
+        assert_eq!(points, *chunk);
+
+        let mut lim = Limit::all(vec![Limit::strict_length(150), Limit::points(3)]);
+        let chunk = &mut points.clone();
+        assert_eq!(Some(Split::At(2)), lim.exceeds(chunk));
+        assert_eq!(4, chunk.len());
+        assert_approx_eq!(0.00135, chunk[2].point().x(), 1e-2);
+    }
+
+    #[test]
+    fn exceeds_all_unchanged() {
+        //the location is near, but there are not enough points, so neither the points nor the location change
+        let points: Vec<Waypoint> = (0..=6)
+            .map(|x| waypoint(f64::from(x) * 0.001, 0.0))
+            .collect();
+        let location = Limit::locations(
+            vec![vec![waypoint(0.0025, 0.00005)]],
+            10,
+            Reuse::Once,
+            &Filter::default(),
+        )
+        .unwrap();
+        let mut lim = Limit::all(vec![location, Limit::points(100)]);
+        let chunk = &mut points[..4].to_vec();
+        assert_eq!(None, lim.exceeds(chunk));
+        assert_eq!(points[..4].to_vec(), *chunk);
+        //the location is still usable, when there are enough points
+        let mut lim = match lim {
+            Limit::All(mut limits) => Limit::all(vec![limits.remove(0), Limit::points(4)]),
+            _ => panic!("unexpected result"),
+        };
+        assert_eq!(Some(Split::At(3)), lim.exceeds(chunk));
+        assert_approx_eq!(0.0025, chunk[3].point().x(), 1e-6);
+
+        let mut lim = Limit::all(vec![Limit::strict_length(100), Limit::points(100)]);
+        let chunk = &mut points[..2].to_vec();
+        assert_eq!(None, lim.exceeds(chunk));
+        assert_eq!(2, chunk.len());
+    }
+
+    fn waypoint_at(time: OffsetDateTime) -> Waypoint {
+        let mut point = waypoint(0.0, 0.0);
+        point.time = Some(time.into());
+        point
+    }
+
+    #[test]
+    fn exceeds_day() {
+        let mut lim = Limit::day("+02:00".parse().unwrap());
+        let points = &mut vec![
+            waypoint_at(datetime!(2021-07-15 21:30 UTC)),
+            waypoint_at(datetime!(2021-07-15 21:59 UTC)),
+            Waypoint::default(),
+        ];
+        assert_eq!(None, lim.exceeds(points));
+        let points = &mut vec![
+            waypoint_at(datetime!(2021-07-15 21:30 UTC)),
+            Waypoint::default(),
+            waypoint_at(datetime!(2021-07-15 22:00 UTC)),
+        ];
+        assert_eq!(Some(Split::Before(2)), lim.exceeds(points));
+    }
+
+    fn stop_waypoint(x: f64, minute: u8) -> Waypoint {
+        let mut point = waypoint(x, 0.0);
+        point.time = timed_waypoint(minute).time;
+        point
+    }
+
+    fn bands() -> Vec<Band> {
+        ["walk:8", "bike:40", "motor"]
+            .iter()
+            .map(|band| band.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn parse_band() {
+        let band: Band = "walk:7.5".parse().unwrap();
+        assert_eq!("walk", band.mode);
+        assert_eq!(Some(7.5), band.max_speed);
+        assert_eq!(None, "motor".parse::<Band>().unwrap().max_speed);
+        assert!("walk:fast".parse::<Band>().is_err());
+        assert!(":7".parse::<Band>().is_err());
+    }
+
+    #[test]
+    fn exceeds_mode_false() {
+        let mut lim = Limit::mode(bands(), Duration::from_secs(2 * 60));
+        //walking with 6.7 km/h
+        let points = &mut (0..5)
+            .map(|minute| stop_waypoint(f64::from(minute) * 0.001, minute))
+            .collect();
+        assert_eq!(None, lim.exceeds(points));
+        //the points are too short to compare the modes
+        points.truncate(3);
+        assert_eq!(None, lim.exceeds(points));
+    }
+
+    #[test]
+    fn exceeds_mode_true() {
+        let mut lim = Limit::mode(bands(), Duration::from_secs(2 * 60));
+        let points = &mut (0..5)
+            .map(|minute| stop_waypoint(f64::from(minute) * 0.001, minute))
+            .collect::<Vec<_>>();
+        //cycling with 33 km/h, the average of the last two minutes is already above walking
+        points.push(stop_waypoint(0.009, 5));
+        assert_eq!(Some(Split::At(3)), lim.exceeds(points));
+        assert_eq!(Some("walk".to_string()), lim.tag(&points[..=3]));
+        assert_eq!(Some("bike".to_string()), lim.tag(&points[3..]));
+    }
+
+    #[test]
+    fn exceeds_dwell_false() {
+        let mut lim = Limit::dwell(20, Duration::from_secs(10 * 60));
+        //still at the stop
+        let points = &mut vec![
+            stop_waypoint(0.0, 0),
+            stop_waypoint(0.001, 1),
+            stop_waypoint(0.001, 20),
+        ];
+        assert_eq!(None, lim.exceeds(points));
+        //stop is too short
+        let points = &mut vec![
+            stop_waypoint(0.0, 0),
+            stop_waypoint(0.001, 1),
+            stop_waypoint(0.001, 5),
+            stop_waypoint(0.002, 6),
+        ];
+        assert_eq!(None, lim.exceeds(points));
+    }
+
+    #[test]
+    fn exceeds_dwell_true() {
+        let mut lim = Limit::dwell(20, Duration::from_secs(10 * 60));
+        let points = &mut vec![
+            stop_waypoint(0.0, 0),
+            stop_waypoint(0.001, 1),
+            stop_waypoint(0.00101, 5),
+            stop_waypoint(0.00102, 12),
+            stop_waypoint(0.002, 13),
+        ];
+        assert_eq!(Some(Split::Skip(1, 4)), lim.exceeds(points));
+        //the points start with the stop
+        let points = &mut vec![
+            stop_waypoint(0.001, 1),
+            stop_waypoint(0.00101, 5),
+            stop_waypoint(0.00102, 12),
+            stop_waypoint(0.002, 13),
+        ];
+        assert_eq!(Some(Split::Skip(0, 3)), lim.exceeds(points));
+    }
+
+    #[test]
+    fn tally_sync() {
+        let points = vec![Waypoint::default(); 5];
+        let mut tally: Tally<usize> = Tally::default();
+        let count = |points: &[Waypoint]| points.len();
+        //built from all points except the last one, which the caller adds
+        let state = tally.sync(&points[..3], count);
+        assert_eq!(2, *state);
+        *state += 1;
+        assert_eq!(3, *tally.sync(&points[..4], count));
+        //built again for points, which were not seen before
+        assert_eq!(1, *tally.sync(&points[..2], count));
+        tally.reset();
+        assert_eq!(2, *tally.sync(&points[..3], count));
+    }
+
+    #[test]
+    fn exceeds_dwell_dense() {
+        //walking with 11 m every 10 seconds, a stop of 40 minutes with some jitter and walking on
+        let start = datetime!(2021-07-15 11:00 UTC);
+        let timed = |x: f64, y: f64, seconds: i64| {
+            let mut point = waypoint(x, y);
+            point.time = Some((start + time::Duration::seconds(seconds)).into());
+            point
+        };
+        let mut points: Vec<Waypoint> = (0..30)
+            .map(|i| timed(f64::from(i) * 0.0001, 0.0, i64::from(i) * 10))
+            .collect();
+        points.extend((30..270).map(|i| {
+            let jitter = f64::from(i % 3) * 0.00002;
+            timed(0.003 + jitter, jitter, i64::from(i) * 10)
+        }));
+        points.extend(
+            (270..300).map(|i| timed(0.003 + f64::from(i - 269) * 0.0001, 0.0, i64::from(i) * 10)),
+        );
+
+        let mut lim = Limit::dwell(50, Duration::from_secs(10 * 60));
+        let mut chunk = vec![];
+        let mut skips = vec![];
+        for point in points {
+            chunk.push(point);
+            if let Some(split) = lim.exceeds(&mut chunk) {
+                let Split::Skip(end, start) = split else {
+                    panic!("unexpected split {split:?}");
+                };
+                skips.push((end, start));
+                chunk = chunk.split_off(start);
+            }
+        }
+        //the stop starts within the radius before the location and ends when walking on
+        assert_eq!(1, skips.len());
+        let (end, start) = skips[0];
+        assert!((25..=30).contains(&end));
+        assert!((270..=275).contains(&start));
+        assert_eq!(300 - start, chunk.len());
+    }
+
+    fn gate(name: &str, p1: Waypoint, p2: Waypoint) -> Route {
+        let mut route = Route::new();
+        route.name = Some(name.to_string());
+        route.points = vec![p1, p2];
+        route
+    }
+
+    #[test]
+    fn wrong_gates() {
+        let Err(err) = Limit::gates("gates.gpx") else {
+            panic!("unexpected result");
+        };
+        assert_eq!(ErrorKind::NotFound, err.kind());
+        assert!(err
+            .to_string()
+            .starts_with("can't read file with gates gates.gpx: "));
+        let Err(err) = Limit::gates("target/debug/pois.gpx") else {
+            panic!("unexpected result");
+        };
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+    }
+
+    #[test]
+    fn exceeds_gate() {
+        let mut lim = Limit::Gate(Box::new(vec![
+            gate("finish", waypoint(0.002, -0.001), waypoint(0.002, 0.001)),
+            gate(
+                "checkpoint",
+                waypoint(0.001, -0.001),
+                waypoint(0.001, 0.001),
+            ),
+        ]));
+        let points = &mut vec![waypoint(0.0, 0.0), waypoint(0.0005, 0.0)];
+        assert_eq!(None, lim.exceeds(points));
+        //both gates are crossed, the nearer one is taken
+        points.push(waypoint(0.003, 0.0));
+        assert_eq!(Some(Split::At(2)), lim.exceeds(points));
+        assert_eq!(4, points.len());
+        assert_approx_eq!(0.001, points[2].point().x(), 1e-6);
+        assert_eq!(Some("checkpoint".to_string()), points[2].name);
+    }
+
+    fn area() -> Route {
+        let mut route = Route::new();
+        route.name = Some("park".to_string());
+        route.points = vec![
+            waypoint(0.001, -0.001),
+            waypoint(0.003, -0.001),
+            waypoint(0.003, 0.001),
+            waypoint(0.001, 0.001),
+        ];
+        route
+    }
+
+    #[test]
+    fn wrong_fences() {
+        let Err(err) = Limit::fences("areas.gpx") else {
+            panic!("unexpected result");
+        };
+        assert_eq!(ErrorKind::NotFound, err.kind());
+        assert!(err
+            .to_string()
+            .starts_with("can't read file with areas areas.gpx: "));
+        //the gates are routes with two points, which enclose no area
+        let Err(err) = Limit::fences("target/debug/gates.gpx") else {
+            panic!("unexpected result");
+        };
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+    }
+
+    #[test]
+    fn exceeds_fence() {
+        let mut lim = Limit::Fence(Box::new(vec![area()]));
+        let points = &mut vec![waypoint(0.0, 0.0), waypoint(0.0005, 0.0)];
+        assert_eq!(None, lim.exceeds(points));
+        //entering the area
+        points.push(waypoint(0.002, 0.0));
+        assert_eq!(Some(Split::At(2)), lim.exceeds(points));
+        assert_approx_eq!(0.001, points[2].point().x(), 1e-6);
+        //passing through the area, the boundary is crossed when entering and again when leaving
+        let points = &mut vec![waypoint(0.0, 0.0), waypoint(0.004, 0.0)];
+        assert_eq!(Some(Split::At(1)), lim.exceeds(points));
+        assert_approx_eq!(0.001, points[1].point().x(), 1e-6);
+        let points = &mut points.split_off(1);
+        assert_eq!(Some(Split::At(1)), lim.exceeds(points));
+        assert_approx_eq!(0.003, points[1].point().x(), 1e-6);
+    }
+
+    #[test]
+    fn tag() {
+        let lim = Limit::any(vec![
+            Limit::points(10),
+            Limit::Fence(Box::new(vec![area()])),
+        ]);
+        let inside = [waypoint(0.001, 0.0), waypoint(0.002, 0.0)];
+        assert_eq!(Some("park".to_string()), lim.tag(&inside));
+        let outside = [waypoint(0.0, 0.0), waypoint(0.001, 0.0)];
+        assert_eq!(None, lim.tag(&outside));
+        assert_eq!(None, Limit::points(10).tag(&inside));
+    }
+
+    #[test]
+    fn exceeds_loop_false() {
+        let mut lim = Limit::loops(20);
+        //a straight line
+        let points = &mut vec![
+            waypoint(0.0, 0.0),
+            waypoint(0.001, 0.0),
+            waypoint(0.002, 0.0),
+            waypoint(0.003, 0.0),
+        ];
+        assert_eq!(None, lim.exceeds(points));
+        //points staying within the distance
+        let points = &mut vec![
+            waypoint(0.0, 0.0),
+            waypoint(0.0001, 0.0),
+            waypoint(0.0, 0.0001),
+            waypoint(0.0, 0.0),
+        ];
+        assert_eq!(None, lim.exceeds(points));
+    }
+
+    #[test]
+    fn exceeds_loop_near() {
+        let mut lim = Limit::loops(20);
+        let points = &mut vec![
+            waypoint(0.0, 0.0),
+            waypoint(0.001, 0.0),
+            waypoint(0.001, 0.001),
+            waypoint(0.0, 0.001),
+            waypoint(0.00005, 0.00005),
+        ];
+        assert_eq!(Some(Split::At(4)), lim.exceeds(points));
+        assert_eq!(5, points.len());
+    }
+
+    #[test]
+    fn exceeds_loop_crossing() {
+        let mut lim = Limit::loops(20);
+        let points = &mut vec![
+            waypoint(0.0, 0.0),
+            waypoint(0.01, 0.0),
+            waypoint(0.01, 0.01),
+            waypoint(0.005, -0.01),
+        ];
+        assert_eq!(Some(Split::At(3)), lim.exceeds(points));
+        assert_eq!(5, points.len());
+        assert_approx_eq!(0.0075, points[3].point().x(), 1e-6);
+        assert_approx_eq!(0.0, points[3].point().y(), 1e-6);
+    }
+
+    #[test]
+    fn exceeds_loop_streaming() {
+        let mut lim = Limit::loops(20);
+        let points = &mut vec![];
+        for index in 0..1000 {
+            points.push(waypoint(f64::from(index) * 0.001, 0.0));
+            assert_eq!(None, lim.exceeds(points));
+        }
+        //back along the line near the first point
+        points.push(waypoint(0.0, 0.0001));
+        assert_eq!(Some(Split::At(1000)), lim.exceeds(points));
+    }
+
+    #[test]
+    fn plan_streaming() {
+        assert_eq!(None, Limit::points(2).plan(&[Waypoint::default()]));
+    }
+
+    #[test]
+    fn plan_parts_by_points() {
+        let points = vec![Waypoint::default(); 11];
+        let lim = Limit::parts(Measure::Points, 6);
+        //10 segments into 6 parts: 2, 1, 2, 2, 1, 2
+        assert_eq!(Some(vec![2, 3, 5, 7, 8]), lim.plan(&points));
+        let lim = Limit::parts(Measure::Points, 20);
+        assert_eq!(Some((1..10).collect()), lim.plan(&points));
+        assert_eq!(Some(vec![]), Limit::parts(Measure::Points, 0).plan(&points));
+        assert_eq!(Some(vec![]), Limit::parts(Measure::Points, 3).plan(&[]));
+    }
+
+    #[test]
+    fn plan_balance_by_length() {
+        //210 m along the equator, roughly 1 m between the points
+        let points: Vec<Waypoint> = (0..=210)
+            .map(|i| waypoint(f64::from(i) * 0.000_008_983, 0.0))
+            .collect();
+        let lim = Limit::balance(Measure::Length, 50);
+        assert_eq!(Some(vec![42, 84, 126, 168]), lim.plan(&points));
+    }
+
+    #[test]
+    fn plan_balance_by_points() {
+        let points = vec![Waypoint::default(); 11];
+        //at most 4 points, so 3 segments per part: 10 segments into 4 parts
+        let lim = Limit::balance(Measure::Points, 4);
+        assert_eq!(Some(vec![3, 5, 8]), lim.plan(&points));
+    }
+
+    #[test]
+    fn label() {
+        let points = [
+            Waypoint::default(),
+            waypoint_at(datetime!(2021-07-15 22:30 UTC)),
+        ];
+        let lim = Limit::day("Europe/Berlin".parse().unwrap());
+        assert_eq!(Some("2021-07-16".to_string()), lim.label(&points));
+        let lim = Limit::any(vec![Limit::points(10), lim]);
+        assert_eq!(Some("2021-07-16".to_string()), lim.label(&points));
+        assert_eq!(None, Limit::points(10).label(&points));
+        assert_eq!(None, Limit::day("+02:00".parse().unwrap()).label(&[]));
+    }
+
+    #[test]
+    fn display() {
+        let lim = Limit::any(vec![
+            Limit::length(50000),
+            Limit::all(vec![
+                Limit::points(2000),
+                Limit::duration(Duration::from_secs(9000)),
+            ]),
+            Limit::elevation(1000, Some(1200), 5),
+        ]);
+        assert_eq!(
+            "any of (length of 50000 m, all of (2000 points, duration of 2h 30m), ascent of 1000 m or descent of 1200 m)",
+            lim.to_string()
+        );
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn candidates_like_brute_force() {
+        //pseudo random locations around a zigzag line
+        let mut seed: u64 = 42;
+        let mut random = || {
+            seed = seed
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (seed >> 11) as f64 / (1_u64 << 53) as f64
+        };
+        let locations: Vec<Waypoint> = (0..300)
+            .map(|_| waypoint(13.4 + random() * 0.05, 52.5 + random() * 0.05))
+            .collect();
+        let points: Vec<Waypoint> = (0..50)
+            .map(|i| waypoint(13.4 + f64::from(i) * 0.001, 52.51 + f64::from(i % 2) * 0.03))
+            .collect();
+        let split_points = SplitPoints::new(locations.clone(), Reuse::Once);
+        let all: Vec<(usize, u32)> = (0..locations.len()).map(|index| (index, 100)).collect();
+
+        let mut found = 0;
+        for segment in points.windows(2) {
+            let segment = (&segment[0], &segment[1]);
+            let candidates = split_points.candidates(segment, 100);
+            assert!(candidates.len() < all.len());
+            let brute_force = Limit::interception_points(&locations, &all, segment);
+            let indexed = Limit::interception_points(&locations, &candidates, segment);
+            assert_eq!(brute_force, indexed);
+            found += indexed.len();
+        }
+        assert!(found > 0);
     }
 
     #[test]
     fn interception_points_not_near() {
         let dist = 34000;
         let line = (&waypoint(-1.0, 0.0), &waypoint(1.0, 0.0));
-        let mut split_points = Box::new(vec![waypoint(-1.5, 1.5)]);
-        let ips = Limit::interception_points(dist, &mut split_points, line);
+        let split_points = vec![waypoint(-1.5, 1.5)];
+        let ips = Limit::interception_points(&split_points, &[(0, dist)], line);
         assert!(ips.is_empty());
     }
 
@@ -200,22 +3006,26 @@ mod tests {
         let line = (&waypoint(-1.0, 0.0), &waypoint(1.0, 0.0));
         let mut wp = waypoint(0.0, 0.2);
         wp.name = Some("Point".to_string());
-        let mut split_points = Box::new(vec![
+        let split_points = vec![
             waypoint(-0.5, 1.5),
             waypoint(-0.1, 0.4),
             wp,
             waypoint(0.5, 0.3),
-        ]);
+        ];
 
-        let mut ips = Limit::interception_points(dist, &mut split_points, line);
+        let mut ips = Limit::interception_points(
+            &split_points,
+            &[(0, dist), (1, dist), (2, dist), (3, dist)],
+            line,
+        );
 
         assert_eq!(2, ips.len());
         let first = ips.pop_first();
-        let first = first.unwrap_or((0, (0, Waypoint::default())));
-        let point = first.1 .1;
-        let first = first.0;
+        let first = first.unwrap_or(((0, 0), Waypoint::default()));
+        let point = first.1;
+        let first = first.0 .0;
         let second = ips.pop_first();
-        let second = second.unwrap_or((0, (0, Waypoint::default()))).0;
+        let second = second.unwrap_or(((0, 0), Waypoint::default())).0 .0;
         let dist = (dist * 1000) as i64; //convert to milimeter
         assert!(second < dist);
         assert!(first < second);
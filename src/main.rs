@@ -1,11 +1,23 @@
-use clap::{Parser, ValueEnum};
-use log::debug;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum};
+use log::{debug, warn};
+use regex::Regex;
 use std::io::Error;
+use std::ops::RangeInclusive;
+use std::path::Path;
 use std::process;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
 
-use gpx_split::limit::Limit;
-use gpx_split::split::{Context, RouteSplitter, Splitter, TrackSplitter};
+use geo::Point;
+use gpx::Waypoint;
+use gpx_split::clock::Zone;
+use gpx_split::device::Preset;
+use gpx_split::io::{read_gpx, read_split_points};
+use gpx_split::limit::{Band, Filter, Limit, Measure, Reuse};
+use gpx_split::pace::Pace;
+use gpx_split::split::{Context, Overlap, RouteSplitter, Splitter, TrackSplitter};
 
 /// A program to split a GPX file into smaller chunks
 #[derive(Parser)]
@@ -14,21 +26,95 @@ struct Arguments {
     /// Path of the file to split
     #[arg(value_name = "PATH_TO_FILE")]
     path: String,
-    /// Track/route will be split, when the maximum is exceeded, points or distance in Meter
-    #[arg(short, long, value_name = "MAXIMUM", default_value_t = 500)]
-    max: u32,
+    /// Track/route will be split, when the maximum is exceeded, points, distance or ascent in Meter, bytes of a file or a duration like 2h30m.
+    /// For splitting by loop, it is the distance in Meter to an earlier point, which closes a loop.
+    /// For splitting by mode, it is a list of speed bands in km/h like walk:7,bike:30,motor.
+    /// For splitting by stage, it is the window of the length in Meter like 70000-90000.
+    /// For splitting by daylight, it is the safety margin before the sunset like 30m.
+    /// For splitting by effort, it is the distance in Meter from the end of a part to a splitting point, if these are given.
+    /// For splitting by gate or area, it is ignored.
+    /// For splitting by day, it is the time zone like Europe/Berlin or an offset to UTC like +02:00.
+    /// Can be repeated, one maximum for each method
+    #[arg(short, long, value_name = "MAXIMUM", default_values_t = ["500".to_string()])]
+    max: Vec<String>,
     /// Objects to split: either routes or the tracks in the GPX file
     #[arg(short, long, value_enum, default_value_t=Trace::Track)]
     trace: Trace,
-    /// Method to split the object: by number of points, length or location
-    #[arg(short, long, value_enum, default_value_t=By::Point)]
-    by: By,
+    /// Method to split the object: by number of points, length, location, stage, time, pace, daylight, effort, size, time gap, day, stop, loop, gate, area, mode or elevation.
+    /// Can be repeated to split when the first of the maximum values is exceeded
+    #[arg(short, long, value_enum, default_values_t=[By::Point])]
+    by: Vec<By>,
+    /// Split only when all maximum values of the repeated methods are exceeded
+    #[arg(long)]
+    all: bool,
+    /// Number of parts with nearly equal size when splitting by number of points, length or effort, the maximum is ignored
+    #[arg(long, value_name = "PARTS")]
+    parts: Option<u32>,
+    /// Split into the fewest parts with nearly equal size, where none is above the maximum of points or length
+    #[arg(long)]
+    balance: bool,
+    /// Split exactly at the maximum length, the last point of a chunk is interpolated
+    #[arg(long)]
+    strict: bool,
+    /// Points at the end of a chunk, which are also the first points of the next chunk: a number of points like 3
+    /// or a distance in Meter like 300m, 0 for disjoint chunks. There is no overlap, when the chunks are not connected, e.g. at a gap.
+    /// At most half of a chunk is carried over and only the number of points and the file size count the carried points
+    #[arg(long, value_name = "OVERLAP", default_value = "1")]
+    overlap: String,
     /// Path for output file, e.g. foo/bar.gpx. The program creates then foo/bar_0.gpx, foo/bar_1.gpx and so on
     #[arg(short, long)]
     output: Option<String>,
-    /// Path to a file that contains the splitting points for a track/route. If not provided, the file to split will be used
+    /// Preset of a device like edge-530 or etrex, which sets the objects to split, the methods with their maximum and the output.
+    /// The arguments of the command line replace the values of the preset
+    #[arg(long, value_name = "DEVICE")]
+    device: Option<String>,
+    /// File with the presets of the user, which replace the built-in presets with the same name
+    #[arg(long, value_name = "PATH", default_value = "gpx_split.ini")]
+    devices: String,
+    /// Path to a file that contains the splitting points, the gates or the areas for a track/route. If not provided, the file to split will be used.
+    /// The splitting points are the waypoints, the points of routes and the named points of tracks.
+    /// Can be repeated to merge the splitting points of several files, the gates and the areas are read from the first file
     #[arg(short, long)]
-    near: Option<String>,
+    near: Vec<String>,
+    /// Reuse of a splitting point on another pass, e.g. on an out-and-back route: once, always
+    /// or the distance in Meter to travel before it is used again.
+    /// With ordered each splitting point is used once in the order of the files, only the next one can split
+    #[arg(long, value_name = "REUSE", default_value = "once")]
+    reuse: String,
+    /// Maximum detour in Meter from the track/route to a splitting point at the end of a stage
+    #[arg(long, value_name = "DETOUR", default_value_t = 1000)]
+    detour: u32,
+    /// Symbol of the splitting points to use, e.g. Campground, the case is ignored
+    #[arg(long, value_name = "SYMBOL")]
+    poi_sym: Option<String>,
+    /// Type of the splitting points to use, the case is ignored
+    #[arg(long, value_name = "TYPE")]
+    poi_type: Option<String>,
+    /// Regular expression for the name of the splitting points to use, e.g. "^(Hut|Hotel)".
+    /// A splitting point can have its own distance like radius=500 in its comment or description
+    #[arg(long, value_name = "REGEX")]
+    poi_name: Option<String>,
+    /// Coordinate of a splitting point like 52.52,13.40, can be repeated
+    #[arg(long, value_name = "LAT,LON", allow_hyphen_values = true)]
+    at: Vec<String>,
+    /// Maximum descent in Meter when splitting by elevation. If not provided, only the ascent is considered
+    #[arg(long, value_name = "DESCENT")]
+    descent: Option<u32>,
+    /// Radius in Meter of a stop when splitting by stop, the maximum is the minimum duration of the stop
+    #[arg(long, value_name = "RADIUS", default_value_t = 50)]
+    radius: u32,
+    /// Start of the first day when splitting by daylight like 2024-12-21T08:00:00+01:00, the next days start at the same time
+    #[arg(long, value_name = "START")]
+    start: Option<String>,
+    /// Model to estimate the travel time when splitting by pace or daylight: naismith, tobler or a flat speed in km/h like flat:20
+    #[arg(long, value_name = "PACE", default_value = "naismith")]
+    pace: String,
+    /// Duration to average the speed when splitting by mode
+    #[arg(long, value_name = "SMOOTHING", default_value = "2m")]
+    smoothing: String,
+    /// Changes of the elevation in Meter, which are ignored when splitting by elevation
+    #[arg(long, value_name = "NOISE", default_value_t = 5)]
+    noise: u32,
 }
 
 /// what to split in the gpx file
@@ -49,34 +135,68 @@ enum By {
     Len,
     /// split by location
     Loc,
+    /// split by elapsed time
+    Time,
+    /// split by the travel time, which is estimated from the distance and the elevation, e.g. for a planned route
+    Pace,
+    /// split into the number of parts with an effort as even as possible, where 100 m of ascent count like 1 km
+    Effort,
+    /// split by the size of a file in bytes, e.g. for a device with an upload limit
+    Size,
+    /// split by daylight, where a chunk ends at the last point reached before the sunset, which is estimated by the pace
+    Daylight,
+    /// split by time gap between two points, e.g. a pause in the recording
+    Gap,
+    /// split by cumulative elevation gain/loss
+    Ele,
+    /// split by calendar day, the files are named after the date
+    Day,
+    /// split by a stop, where the points stay within a radius for a minimum duration
+    Stop,
+    /// split by a loop, where the track/route crosses itself or returns within a distance to an earlier point
+    Loop,
+    /// split by crossing a gate, e.g. a start/finish line, which is a route with two points
+    Gate,
+    /// split by entering or leaving an area, which is enclosed by a route, the type of a track/route is the name of its area
+    Area,
+    /// split by a change of the mode, e.g. from walking to cycling, the type of a track/route is the mode
+    Mode,
+    /// split by stage, which ends at the splitting point with the smallest detour, once the length is within a window
+    Stage,
 }
 
 fn main() {
     env_logger::init();
     let now = Instant::now();
 
-    let args = Arguments::parse();
+    let matches = Arguments::command().get_matches();
+    let mut args = Arguments::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    let files = apply_device(&mut args, &matches);
+    let limit = create_limit(&args);
     let path = args.path;
+    let overlap = parse_overlap(&args.overlap);
     let trace = args.trace;
-    let by = args.by;
-    let max = args.max;
     let out = args.output;
-    let near = args.near;
 
-    let limit = match by {
-        By::Len => Limit::length(max),
-        By::Point => Limit::points(max),
-        By::Loc => Limit::location(&near.unwrap_or(path.clone()), max),
-    };
+    let res = limit
+        .and_then(|limit| match trace {
+            Trace::Route => {
+                let splitter = RouteSplitter::new(limit).with_overlap(overlap);
+                run(&path, out, Box::new(splitter))
+            }
+            Trace::Track => {
+                let splitter = TrackSplitter::new(limit).with_overlap(overlap);
+                run(&path, out, Box::new(splitter))
+            }
+        })
+        .unwrap_or_else(|err| {
+            eprintln!("Problem processing GPX file: {err}");
+            process::exit(1);
+        });
 
-    let res = match trace {
-        Trace::Route => run(&path, out, Box::new(RouteSplitter::new(limit))),
-        Trace::Track => run(&path, out, Box::new(TrackSplitter::new(limit))),
+    if let Some(files) = files.filter(|files| res > *files) {
+        warn!("Created {res} files, but the device can hold only {files} tracks or routes");
     }
-    .unwrap_or_else(|err| {
-        eprintln!("Problem processing GPX file: {err}");
-        process::exit(1);
-    });
 
     debug!(
         "Splitting source into {} files took {} microseconds.",
@@ -85,6 +205,267 @@ fn main() {
     );
 }
 
+/// Expands the preset of the device into the arguments, which are not given on the command line.
+/// Returns the number of tracks or routes, the device can hold.
+fn apply_device(args: &mut Arguments, matches: &ArgMatches) -> Option<usize> {
+    let name = args.device.as_ref()?;
+    let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    //the default file with the presets of the user is optional
+    let path = (given("devices") || Path::new(&args.devices).exists()).then_some(&args.devices);
+    let preset = Preset::find(name, path.map(String::as_str)).unwrap_or_else(|err| {
+        eprintln!("Invalid device {name}: {err}");
+        process::exit(1);
+    });
+    debug!("using the preset {:?}", preset);
+
+    if let Some(trace) = preset.trace.filter(|_| !given("trace")) {
+        args.trace = parse_value(&trace);
+    }
+    if !given("by") && !given("max") && !preset.limits.is_empty() {
+        (args.by, args.max) = preset
+            .limits
+            .iter()
+            .map(|(by, max)| (parse_value(by), max.clone()))
+            .unzip();
+    }
+    args.all |= preset.all;
+    args.strict |= preset.strict;
+    if let (None, Some(dir)) = (&args.output, &preset.output) {
+        let file = Path::new(&args.path).file_name().unwrap_or_default();
+        args.output = Some(Path::new(dir).join(file).to_string_lossy().into_owned());
+    }
+    preset.files
+}
+
+fn create_limit(args: &Arguments) -> Result<Limit, Error> {
+    if args.by.len() != args.max.len() {
+        eprintln!(
+            "Each method to split needs a maximum, found {} methods and {} maximum values",
+            args.by.len(),
+            args.max.len()
+        );
+        process::exit(1);
+    }
+
+    if args.parts.is_some() || args.balance {
+        return create_balanced_limit(args);
+    }
+
+    let mut limits: Vec<Limit> = args
+        .by
+        .iter()
+        .zip(args.max.iter())
+        .map(|(by, max)| {
+            Ok(match by {
+                By::Len if args.strict => Limit::strict_length(parse_max(max)),
+                By::Len => Limit::length(parse_max(max)),
+                By::Point => Limit::points(parse_max(max)),
+                By::Loc => {
+                    let filter = parse_filter(args);
+                    Limit::locations(
+                        split_points(args)?,
+                        parse_max(max),
+                        parse_reuse(&args.reuse),
+                        &filter,
+                    )?
+                }
+                By::Stage => Limit::stages(
+                    split_points(args)?,
+                    parse_window(max),
+                    args.detour,
+                    &parse_filter(args),
+                )?,
+                By::Time => Limit::duration(parse_duration(max)),
+                By::Pace => Limit::pace(parse_pace(&args.pace), parse_duration(max)),
+                By::Daylight => Limit::daylight(
+                    parse_start(args.start.as_deref()),
+                    parse_pace(&args.pace),
+                    parse_duration(max),
+                ),
+                By::Gap => Limit::gap(parse_duration(max)),
+                By::Ele => Limit::elevation(parse_max(max), args.descent, args.noise),
+                By::Day => Limit::day(parse_zone(max)),
+                By::Size => Limit::size(u64::from(parse_max(max))),
+                By::Effort => create_optimal_limit(args)?,
+                By::Stop => Limit::dwell(args.radius, parse_duration(max)),
+                By::Loop => Limit::loops(parse_max(max)),
+                By::Gate => Limit::gates(args.near.first().unwrap_or(&args.path))?,
+                By::Area => Limit::fences(args.near.first().unwrap_or(&args.path))?,
+                By::Mode => Limit::mode(parse_bands(max), parse_duration(&args.smoothing)),
+            })
+        })
+        .collect::<Result<_, Error>>()?;
+
+    Ok(match limits.len() {
+        1 => limits.remove(0),
+        _ if args.all => Limit::all(limits),
+        _ => Limit::any(limits),
+    })
+}
+
+fn create_balanced_limit(args: &Arguments) -> Result<Limit, Error> {
+    let measure = match args.by.as_slice() {
+        [By::Point] => Measure::Points,
+        [By::Len] => Measure::Length,
+        [By::Effort] => return create_optimal_limit(args),
+        _ => {
+            eprintln!(
+                "Balanced parts need a single method to split, either by point, by len or by effort"
+            );
+            process::exit(1);
+        }
+    };
+    Ok(match args.parts {
+        Some(parts) => Limit::parts(measure, parts),
+        None => Limit::balance(measure, parse_max(&args.max[0])),
+    })
+}
+
+fn create_optimal_limit(args: &Arguments) -> Result<Limit, Error> {
+    let Some(parts) = args.parts else {
+        eprintln!("Splitting by effort needs the number of parts");
+        process::exit(1);
+    };
+    //the parts end near the splitting points only, when they are given
+    let near = if args.near.is_empty() && args.at.is_empty() {
+        None
+    } else {
+        Some((split_points(args)?, parse_max(&args.max[0])))
+    };
+    Limit::optimal(parts, near, &parse_filter(args))
+}
+
+// Returns the splitting points of each source, the limit filters and merges them.
+fn split_points(args: &Arguments) -> Result<Vec<Vec<Waypoint>>, Error> {
+    if args.near.is_empty() && args.at.is_empty() {
+        //the points of the file to split are not splitting points, only its waypoints
+        return Ok(vec![read_gpx(&args.path)?.waypoints]);
+    }
+    let mut sources = vec![];
+    for path in &args.near {
+        sources.push(read_split_points(path)?);
+    }
+    sources.extend(
+        args.at
+            .iter()
+            .map(|coordinate| vec![parse_coordinate(coordinate)]),
+    );
+    Ok(sources)
+}
+
+fn parse_coordinate(coordinate: &str) -> Waypoint {
+    let parsed = coordinate
+        .split_once(',')
+        .and_then(|(lat, lon)| Some((lat.trim().parse().ok()?, lon.trim().parse().ok()?)));
+    match parsed {
+        Some((lat, lon)) if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) => {
+            Waypoint::new(Point::new(lon, lat))
+        }
+        _ => {
+            eprintln!(
+                "Invalid coordinate {coordinate}, expected latitude and longitude like 52.52,13.40"
+            );
+            process::exit(1);
+        }
+    }
+}
+
+fn parse_max(max: &str) -> u32 {
+    max.parse().unwrap_or_else(|err| {
+        eprintln!("Invalid maximum {max}: {err}");
+        process::exit(1);
+    })
+}
+
+fn parse_window(window: &str) -> RangeInclusive<u32> {
+    match window.split_once('-') {
+        Some((min, max)) if parse_max(min) <= parse_max(max) => parse_max(min)..=parse_max(max),
+        _ => {
+            eprintln!("Invalid window {window}, expected the minimum and maximum like 70000-90000");
+            process::exit(1);
+        }
+    }
+}
+
+fn parse_duration(max: &str) -> Duration {
+    humantime::parse_duration(max).unwrap_or_else(|err| {
+        eprintln!("Invalid duration {max}: {err}");
+        process::exit(1);
+    })
+}
+
+fn parse_bands(bands: &str) -> Vec<Band> {
+    bands
+        .split(',')
+        .map(|band| {
+            band.parse().unwrap_or_else(|err| {
+                eprintln!("Invalid speed band {band}: {err}");
+                process::exit(1);
+            })
+        })
+        .collect()
+}
+
+fn parse_filter(args: &Arguments) -> Filter {
+    let name = args.poi_name.as_ref().map(|pattern| {
+        Regex::new(pattern).unwrap_or_else(|err| {
+            eprintln!("Invalid pattern for the name {pattern}: {err}");
+            process::exit(1);
+        })
+    });
+    Filter {
+        symbol: args.poi_sym.clone(),
+        kind: args.poi_type.clone(),
+        name,
+    }
+}
+
+fn parse_start(start: Option<&str>) -> OffsetDateTime {
+    let Some(start) = start else {
+        eprintln!("Splitting by daylight needs the start of the first day");
+        process::exit(1);
+    };
+    OffsetDateTime::parse(start, &Rfc3339).unwrap_or_else(|err| {
+        eprintln!("Invalid start {start}: {err}");
+        process::exit(1);
+    })
+}
+
+fn parse_pace(pace: &str) -> Pace {
+    pace.parse().unwrap_or_else(|err| {
+        eprintln!("Invalid pace {pace}: {err}");
+        process::exit(1);
+    })
+}
+
+fn parse_value<T: ValueEnum>(value: &str) -> T {
+    T::from_str(value, true).unwrap_or_else(|err| {
+        eprintln!("Invalid value {value} of the device: {err}");
+        process::exit(1);
+    })
+}
+
+fn parse_overlap(overlap: &str) -> Overlap {
+    overlap.parse().unwrap_or_else(|err| {
+        eprintln!("Invalid overlap {overlap}: {err}");
+        process::exit(1);
+    })
+}
+
+fn parse_reuse(reuse: &str) -> Reuse {
+    reuse.parse().unwrap_or_else(|err| {
+        eprintln!("Invalid reuse {reuse}: {err}");
+        process::exit(1);
+    })
+}
+
+fn parse_zone(zone: &str) -> Zone {
+    zone.parse().unwrap_or_else(|err| {
+        eprintln!("Invalid time zone {zone}: {err}");
+        process::exit(1);
+    })
+}
+
 fn run<T: 'static>(
     path: &str,
     output: Option<String>,
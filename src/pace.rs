@@ -0,0 +1,161 @@
+use gpx::Waypoint;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::geo::distance;
+
+/// A model to estimate the travel time from the distance and the change of the elevation,
+/// e.g. for a planned route without timestamps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pace {
+    /// Naismith's rule for hiking: 5 km/h plus one hour for each 600 m of ascent
+    Naismith,
+    /// Tobler's hiking function, where the speed of 6 km/h decreases exponentially with the slope
+    Tobler,
+    /// a flat speed in km/h, e.g. for cycling, where the elevation is ignored
+    Flat(f64),
+}
+
+impl Pace {
+    /// Estimates the time to travel along the points.
+    /// A segment is considered to be flat, when one of its points has no elevation.
+    ///
+    /// ```
+    /// use gpx::Waypoint;
+    /// use geo::Point;
+    /// use gpx_split::pace::*;
+    /// use std::time::Duration;
+    ///
+    /// let mut p1 = Waypoint::new(Point::new(0.0, 0.0));
+    /// p1.elevation = Some(0.0);
+    /// let mut p2 = Waypoint::new(Point::new(0.0, 0.0));
+    /// p2.elevation = Some(600.0);
+    ///
+    /// assert_eq!(Duration::from_secs(3600), Pace::Naismith.estimate(&[p1, p2]));
+    /// ```
+    #[must_use]
+    pub fn estimate(&self, points: &[Waypoint]) -> Duration {
+        let hours: f64 = points
+            .windows(2)
+            .map(|segment| self.hours(&segment[0], &segment[1]))
+            .sum();
+        Duration::try_from_secs_f64(hours * 3600.0).unwrap_or(Duration::MAX)
+    }
+
+    // Estimates the hours to travel from one point to the next.
+    fn hours(&self, from: &Waypoint, to: &Waypoint) -> f64 {
+        let km = distance(from, to) / 1000.0;
+        let climb = match (from.elevation, to.elevation) {
+            (Some(from), Some(to)) => to - from,
+            _ => 0.0,
+        };
+        match self {
+            Pace::Naismith => km / 5.0 + climb.max(0.0) / 600.0,
+            Pace::Tobler if km > 0.0 => {
+                //the speed on a very steep slope, e.g. a jitter of the elevation, would be zero
+                const MIN_SPEED: f64 = 0.1;
+                let slope = climb / (km * 1000.0);
+                km / (6.0 * (-3.5 * (slope + 0.05).abs()).exp()).max(MIN_SPEED)
+            }
+            Pace::Tobler => 0.0,
+            Pace::Flat(speed) => km / speed,
+        }
+    }
+}
+
+impl FromStr for Pace {
+    type Err = String;
+
+    /// Parses either naismith, tobler or a flat speed in km/h like flat:20.
+    fn from_str(pace: &str) -> Result<Self, Self::Err> {
+        match pace.split_once(':') {
+            None if pace == "naismith" => Ok(Pace::Naismith),
+            None if pace == "tobler" => Ok(Pace::Tobler),
+            Some(("flat", speed)) => match speed.parse::<f64>() {
+                Ok(speed) if speed > 0.0 && speed.is_finite() => Ok(Pace::Flat(speed)),
+                _ => Err(format!("invalid speed in km/h: {speed}")),
+            },
+            _ => Err(format!(
+                "unknown pace: {pace}, expected naismith, tobler or flat:<km/h>"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Pace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pace::Naismith => write!(f, "Naismith's rule"),
+            Pace::Tobler => write!(f, "Tobler's hiking function"),
+            Pace::Flat(speed) => write!(f, "{speed} km/h"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+
+    use super::*;
+
+    fn waypoint(x: f64, elevation: Option<f64>) -> Waypoint {
+        let mut point = Waypoint::new(Point::new(x, 0.0));
+        point.elevation = elevation;
+        point
+    }
+
+    fn minutes(pace: Pace, points: &[Waypoint]) -> u64 {
+        (pace.estimate(points).as_secs_f64() / 60.0).round() as u64
+    }
+
+    #[test]
+    fn estimate_flat() {
+        //about 11.1 km on the equator
+        let points = [waypoint(0.0, None), waypoint(0.1, None)];
+        assert_eq!(134, minutes(Pace::Naismith, &points));
+        assert_eq!(33, minutes(Pace::Flat(20.0), &points));
+        //Tobler is about 5 km/h on the flat, the fastest is 6 km/h on a slight downhill
+        assert_eq!(133, minutes(Pace::Tobler, &points));
+        assert_eq!(0, minutes(Pace::Tobler, &points[..1]));
+    }
+
+    #[test]
+    fn estimate_slope() {
+        //up and down again on about 2.2 km with a slope of 20%
+        let points = [
+            waypoint(0.0, Some(0.0)),
+            waypoint(0.01, Some(222.6)),
+            waypoint(0.02, Some(0.0)),
+        ];
+        assert_eq!(49, minutes(Pace::Naismith, &points));
+        assert_eq!(46, minutes(Pace::Tobler, &points));
+        assert_eq!(
+            minutes(Pace::Flat(12.0), &points[..2]),
+            minutes(Pace::Flat(12.0), &points[1..])
+        );
+        //the segment without an elevation is flat
+        assert_eq!(
+            13,
+            minutes(Pace::Naismith, &[waypoint(0.0, None), points[1].clone()])
+        );
+    }
+
+    #[test]
+    fn estimate_steep() {
+        //a jump of 50 m in the elevation between two points, which are 1 cm apart
+        let points = [waypoint(0.0, Some(0.0)), waypoint(0.000_000_09, Some(50.0))];
+        assert_eq!(0, minutes(Pace::Tobler, &points));
+        assert!(Pace::Tobler.estimate(&points) > Duration::ZERO);
+    }
+
+    #[test]
+    fn parse_pace() {
+        assert_eq!(Ok(Pace::Naismith), "naismith".parse());
+        assert_eq!(Ok(Pace::Tobler), "tobler".parse());
+        assert_eq!(Ok(Pace::Flat(18.5)), "flat:18.5".parse());
+        assert!("flat:0".parse::<Pace>().is_err());
+        assert!("flat".parse::<Pace>().is_err());
+        assert!("bike".parse::<Pace>().is_err());
+    }
+}
@@ -1,11 +1,15 @@
 use gpx::{Gpx, Route, Track, TrackSegment, Waypoint};
 use log::debug;
+use std::collections::HashSet;
+use std::iter;
+use std::mem;
+use std::str::FromStr;
 use std::thread;
 use std::thread::JoinHandle;
 
-use crate::geo::fit_bounds;
-use crate::io::{append_index, read_gpx, write_gpx};
-use crate::limit::Limit;
+use crate::geo::{distance, fit_bounds};
+use crate::io::{append_index, read_gpx, serialized_size, write_gpx};
+use crate::limit::{Carried, Limit, Split};
 
 type Result<T> = std::result::Result<T, std::io::Error>;
 
@@ -36,6 +40,7 @@ impl<T> Context<T> {
         let gpx = read_gpx(self.input_file.as_str())?;
         let origin = self.splitter.traces(gpx.clone());
         let len = origin.len();
+        self.splitter.prepare(&gpx);
         let new_traces = self.splitter.split(&origin);
         if new_traces.len() > len {
             debug!("{} traces after splitting", new_traces.len());
@@ -47,9 +52,10 @@ impl<T> Context<T> {
     fn write(&self, gpx: &Gpx, traces: Vec<T>) -> Result<usize> {
         let mut handles = Vec::with_capacity(traces.len());
         let path = self.output_file.clone().unwrap_or(self.input_file.clone());
+        let suffixes = self.suffixes(&traces);
 
-        traces.iter().enumerate().for_each(|(index, trace)| {
-            let h = self.splitter.write(&path, gpx, trace, index);
+        traces.iter().zip(suffixes).for_each(|(trace, suffix)| {
+            let h = self.splitter.write(&path, gpx, trace, &suffix);
             handles.push(h);
         });
 
@@ -59,6 +65,16 @@ impl<T> Context<T> {
 
         Ok(traces.len())
     }
+
+    // The labels of the traces are used as suffixes for the files, when every trace has a unique one.
+    // Otherwise the index of the trace is the suffix.
+    fn suffixes(&self, traces: &[T]) -> Vec<String> {
+        let labels: Option<Vec<String>> = traces.iter().map(|t| self.splitter.label(t)).collect();
+        match labels {
+            Some(labels) if labels.iter().collect::<HashSet<_>>().len() == labels.len() => labels,
+            _ => (0..traces.len()).map(|index| index.to_string()).collect(),
+        }
+    }
 }
 
 //--------------------------------------------------------------
@@ -67,20 +83,79 @@ impl<T> Context<T> {
 pub trait Splitter<T> {
     /// Returns the trace to split.
     fn traces(&self, gpx: Gpx) -> Vec<T>;
+    /// Prepares the splitting of the source, e.g. reserves the bytes of the data, which is copied into each file.
+    fn prepare(&mut self, gpx: &Gpx);
     /// Split the origin into new vector.
     fn split(&mut self, origin: &[T]) -> Vec<T>;
-    /// Write one new trace into a file.
-    fn write(&self, path: &str, gpx: &Gpx, trace: &T, counter: usize) -> JoinHandle<Result<()>>;
+    /// Returns the label of a new trace, e.g. the date of its points.
+    fn label(&self, trace: &T) -> Option<String>;
+    /// Write one new trace into a file, where the suffix is appended to the name.
+    fn write(&self, path: &str, gpx: &Gpx, trace: &T, suffix: &str) -> JoinHandle<Result<()>>;
+}
+
+/// Points at the end of a chunk, which are carried over into the next chunk, when it is split at a point.
+/// The overlap gives a device some context when switching the files. The points are never carried over,
+/// when the chunks are not connected, e.g. at a gap or a stop. At most half of the points of a chunk are
+/// carried over, so that each chunk moves on. The limits check the points of a chunk from the point at the split,
+/// only the number of points and the size of a file count the carried points too.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Overlap {
+    /// the number of points, 0 means disjoint chunks and 1 shares the point at the split (default)
+    Points(usize),
+    /// the distance in meter before the point at the split, which is always shared
+    Meters(u32),
+}
+
+impl Default for Overlap {
+    fn default() -> Self {
+        Overlap::Points(1)
+    }
+}
+
+impl Overlap {
+    /// Returns the number of points at the end of the chunk, which are carried over.
+    fn carry(&self, chunk: &[Waypoint]) -> usize {
+        let count = match self {
+            Overlap::Points(count) => *count,
+            Overlap::Meters(max) => {
+                let mut len = 0.0;
+                let within = chunk.windows(2).rev().take_while(|segment| {
+                    len += distance(&segment[0], &segment[1]);
+                    len <= f64::from(*max)
+                });
+                1 + within.count()
+            }
+        };
+        //at most half of the chunk, rounded up, so that the next chunk moves on
+        count.min(chunk.len().div_ceil(2))
+    }
+}
+
+impl FromStr for Overlap {
+    type Err = String;
+
+    /// Parses either a number of points like 3 or a distance in meter like 300m.
+    fn from_str(overlap: &str) -> std::result::Result<Self, Self::Err> {
+        let parsed = match overlap.strip_suffix('m') {
+            Some(meters) => meters.parse().map(Overlap::Meters),
+            None => overlap.parse().map(Overlap::Points),
+        };
+        parsed.map_err(|_| {
+            format!("expected a number of points or a distance in meter like 300m: {overlap}")
+        })
+    }
 }
 
 /// Splitter for routes.
 pub struct RouteSplitter {
     limit: Limit,
+    overlap: Overlap,
 }
 
 /// Splitter for tracks.
 pub struct TrackSplitter {
     limit: Limit,
+    overlap: Overlap,
 }
 
 //--------------------------------------------------------------
@@ -90,44 +165,50 @@ impl Splitter<Route> for RouteSplitter {
         gpx.routes
     }
 
+    fn prepare(&mut self, gpx: &Gpx) {
+        if self.limit.budget().is_some() {
+            let mut around = gpx.clone();
+            let headers = mem::take(&mut around.routes)
+                .iter()
+                .map(|route| Gpx {
+                    version: gpx.version,
+                    routes: vec![RouteSplitter::clone_route(route, &[], None)],
+                    ..Gpx::default()
+                })
+                .collect::<Vec<_>>();
+            self.limit.reserve(reserved_size(&around, &headers));
+        }
+    }
+
     /// splits the given routes into new routes where the number of points of that route are limited
     fn split(&mut self, routes: &[Route]) -> Vec<Route> {
-        let mut new_routes = Vec::new();
-        let mut points = Vec::new();
-        routes.iter().for_each(|route| {
-            route.points.iter().for_each(|point| {
-                points.push(point.clone());
-
-                if self.limit.exceeds(&mut points) {
-                    new_routes.push(RouteSplitter::clone_route(route, &points));
-                    //clear points, starting with last one
-                    points = clear_points(&points);
-                }
-            });
-        });
-
-        //this condition will be true in most cases
-        //but it can happen that we split at the end of a route, in this case we have only one point
-        if points.len() > 1 {
-            if let Some(route) = routes.last() {
-                new_routes.push(RouteSplitter::clone_route(route, &points));
-            }
-        }
+        split_traces(
+            &mut self.limit,
+            self.overlap,
+            routes,
+            |route| route.points.clone(),
+            RouteSplitter::clone_route,
+        )
+    }
 
-        new_routes
+    fn label(&self, route: &Route) -> Option<String> {
+        self.limit.label(&route.points)
     }
 
     /// Writes the given route into a new file.
-    fn write(&self, path: &str, gpx: &Gpx, route: &Route, index: usize) -> JoinHandle<Result<()>> {
+    fn write(&self, path: &str, gpx: &Gpx, route: &Route, suffix: &str) -> JoinHandle<Result<()>> {
         let path = path.to_string();
+        let suffix = suffix.to_string();
         let gpx = gpx.clone();
         let mut route = route.clone();
+        let budget = self.limit.budget();
         thread::spawn(move || {
             let mut gpx = fit_bounds(gpx, &route.points);
-            route.name = append_index(route.name, index);
+            route.name = append_index(route.name, &suffix);
             gpx.routes.clear();
             gpx.routes.push(route);
-            write_gpx(gpx, &path, index)
+            write_gpx(gpx, &path, &suffix, budget)?;
+            Ok(())
         })
     }
 }
@@ -136,12 +217,26 @@ impl RouteSplitter {
     ///Constructs a new [Splitter] for a [Route].
     #[must_use]
     pub fn new(limit: Limit) -> Self {
-        RouteSplitter { limit }
+        RouteSplitter {
+            limit,
+            overlap: Overlap::default(),
+        }
     }
 
-    fn clone_route(src_route: &Route, points: &[Waypoint]) -> Route {
+    /// Sets the points, which are carried over into the next route.
+    #[must_use]
+    pub fn with_overlap(mut self, overlap: Overlap) -> Self {
+        debug!("overlap of the routes: {:?}", overlap);
+        self.overlap = overlap;
+        self
+    }
+
+    fn clone_route(src_route: &Route, points: &[Waypoint], tag: Option<String>) -> Route {
         let mut cloned_route = src_route.clone();
         cloned_route.points = points.to_vec();
+        if tag.is_some() {
+            cloned_route.type_ = tag;
+        }
         cloned_route
     }
 }
@@ -153,55 +248,53 @@ impl Splitter<Track> for TrackSplitter {
         gpx.tracks
     }
 
-    /// splits the given tracks into new tracks where the number of points of that tracks are limited
-    fn split(&mut self, tracks: &[Track]) -> Vec<Track> {
-        let mut new_tracks = Vec::new();
-        let mut points = Vec::new();
-        tracks.iter().for_each(|track| {
-            track
-                .segments
+    fn prepare(&mut self, gpx: &Gpx) {
+        if self.limit.budget().is_some() {
+            let mut around = gpx.clone();
+            let headers = mem::take(&mut around.tracks)
                 .iter()
-                .flat_map(|segment| segment.points.iter())
-                .for_each(|point| {
-                    points.push(point.clone());
-
-                    //create a new track when the points exceed a limit
-                    if self.limit.exceeds(&mut points) {
-                        new_tracks.push(TrackSplitter::clone_track(track, &points));
-                        //clear points, starting with last one
-                        points = clear_points(&points);
-                    }
-                });
-        });
-        //this condition will be true in most cases
-        //but it can happen that we split at the end of a track, in this case we have only one point
-        if points.len() > 1 {
-            if let Some(track) = tracks.last() {
-                new_tracks.push(TrackSplitter::clone_track(track, &points));
-            }
+                .map(|track| Gpx {
+                    version: gpx.version,
+                    tracks: vec![TrackSplitter::clone_track(track, &[], None)],
+                    ..Gpx::default()
+                })
+                .collect::<Vec<_>>();
+            self.limit.reserve(reserved_size(&around, &headers));
         }
+    }
+
+    /// splits the given tracks into new tracks where the number of points of that tracks are limited
+    fn split(&mut self, tracks: &[Track]) -> Vec<Track> {
+        split_traces(
+            &mut self.limit,
+            self.overlap,
+            tracks,
+            TrackSplitter::points,
+            TrackSplitter::clone_track,
+        )
+    }
 
-        new_tracks
+    fn label(&self, track: &Track) -> Option<String> {
+        self.limit.label(&TrackSplitter::points(track))
     }
 
     /// Writes the given track into a new file.
-    fn write(&self, path: &str, gpx: &Gpx, track: &Track, index: usize) -> JoinHandle<Result<()>> {
+    fn write(&self, path: &str, gpx: &Gpx, track: &Track, suffix: &str) -> JoinHandle<Result<()>> {
         let path = path.to_string();
+        let suffix = suffix.to_string();
         let gpx = gpx.clone();
         let mut track = track.clone();
+        let budget = self.limit.budget();
         thread::spawn(move || {
-            let points: Vec<Waypoint> = track
-                .segments
-                .iter()
-                .flat_map(|s| s.points.iter().cloned())
-                .collect();
+            let points = TrackSplitter::points(&track);
             let mut gpx = fit_bounds(gpx, &points);
-            track.name = append_index(track.name, index);
+            track.name = append_index(track.name, &suffix);
             gpx.tracks.clear();
             gpx.tracks.push(track);
             gpx.tracks.shrink_to_fit();
 
-            write_gpx(gpx, &path, index)
+            write_gpx(gpx, &path, &suffix, budget)?;
+            Ok(())
         })
     }
 }
@@ -210,28 +303,178 @@ impl TrackSplitter {
     /// Constructs a new [Splitter] for a [Track].
     #[must_use]
     pub fn new(limit: Limit) -> Self {
-        TrackSplitter { limit }
+        TrackSplitter {
+            limit,
+            overlap: Overlap::default(),
+        }
+    }
+
+    /// Sets the points, which are carried over into the next track.
+    #[must_use]
+    pub fn with_overlap(mut self, overlap: Overlap) -> Self {
+        debug!("overlap of the tracks: {:?}", overlap);
+        self.overlap = overlap;
+        self
     }
 
-    /// clone the source track and add new track segment with the points
-    fn clone_track(src_track: &Track, points: &[Waypoint]) -> Track {
+    /// collect the points of all segments of the track
+    fn points(track: &Track) -> Vec<Waypoint> {
+        track
+            .segments
+            .iter()
+            .flat_map(|s| s.points.iter().cloned())
+            .collect()
+    }
+
+    /// clone the source track and add new track segment with the points, the tag replaces the type
+    fn clone_track(src_track: &Track, points: &[Waypoint], tag: Option<String>) -> Track {
         let mut track_segment = TrackSegment::new();
         track_segment.points = points.to_vec();
 
         let mut cloned_track = src_track.clone();
         cloned_track.segments = vec![track_segment];
+        if tag.is_some() {
+            cloned_track.type_ = tag;
+        }
         cloned_track
     }
 }
 
 //--------------------------------------------------------------
 
-// clear the points and add the previous last one as the first
-fn clear_points(points: &[Waypoint]) -> Vec<Waypoint> {
-    if let Some(last) = points.last() {
-        return vec![last.clone()];
+// Splits the points of all traces into chunks, where the points function returns the points of a trace
+// and the clone function creates a new trace from the source trace with the points and the tag of a chunk.
+fn split_traces<T>(
+    limit: &mut Limit,
+    overlap: Overlap,
+    traces: &[T],
+    points: fn(&T) -> Vec<Waypoint>,
+    clone: fn(&T, &[Waypoint], Option<String>) -> T,
+) -> Vec<T> {
+    //some limits need all points in advance to plan the indices where to split
+    let all_points: Vec<Waypoint> = traces.iter().flat_map(points).collect();
+    let cuts = limit.plan(&all_points);
+
+    let mut new_traces = Vec::new();
+    //the points of the chunk, which start at the point of the previous split and are checked by the limit
+    let mut chunk_points = Vec::new();
+    //the points at the end of the previous chunk, which are written before the points of the chunk
+    let mut carried = Vec::new();
+    //false, when the first point of the chunk is not written, because it ends the previous chunk
+    let mut shared = true;
+    let mut index = 0;
+    traces.iter().for_each(|trace| {
+        points(trace).into_iter().for_each(|point| {
+            chunk_points.push(point);
+
+            //create a new trace when the points exceed a limit,
+            //this is repeated when a limit splits a long segment into several chunks
+            loop {
+                let split = match &cuts {
+                    Some(cuts) => cuts
+                        .binary_search(&index)
+                        .is_ok()
+                        .then(|| Split::At(chunk_points.len() - 1)),
+                    None => {
+                        let after = Carried {
+                            points: &carried,
+                            shared,
+                        };
+                        limit.exceeds_after(after, &mut chunk_points)
+                    }
+                };
+                let Some(split) = split else {
+                    break;
+                };
+                let (chunk, connected) = divide(&mut chunk_points, &split);
+                let written = written(&carried, shared, &chunk);
+                //a single point, e.g. before a stop, is not a trace, it stays in the next chunk if it is connected
+                (carried, shared) = if written.len() > 1 {
+                    new_traces.push(clone(trace, &written, limit.tag(&chunk)));
+                    if connected {
+                        let count = overlap.carry(&written);
+                        (written[written.len() - count..].to_vec(), count > 0)
+                    } else {
+                        (Vec::new(), true)
+                    }
+                } else {
+                    (Vec::new(), true)
+                };
+                //the point at the split is the first point of the next chunk and not carried
+                if shared {
+                    carried.pop();
+                }
+                //the chunk does not move on, when it is split at its first point
+                if cuts.is_some() || chunk_points.len() < 2 || (connected && chunk.len() < 2) {
+                    break;
+                }
+            }
+            index += 1;
+        });
+    });
+
+    limit.finish();
+
+    //this condition will be true in most cases
+    //but it can happen that we split at the end of a trace, in this case we have only the point at the split
+    let written = written(&carried, shared, &chunk_points);
+    if chunk_points.len() > 1 && written.len() > 1 {
+        if let Some(trace) = traces.last() {
+            new_traces.push(clone(trace, &written, limit.tag(&chunk_points)));
+        }
     }
-    vec![]
+
+    new_traces
+}
+
+// Returns the bytes of the data around the points in each file. This is the source without the traces,
+// which is copied into each file, and the largest of the traces without points.
+fn reserved_size(around: &Gpx, headers: &[Gpx]) -> u64 {
+    //for the suffixes of the names and the bounds, which are changed for each file
+    const SLACK: u64 = 128;
+    let size = |gpx: &Gpx| serialized_size(gpx).unwrap_or_default();
+    let empty = size(&Gpx {
+        version: around.version,
+        ..Gpx::default()
+    });
+    let header = headers
+        .iter()
+        .map(|gpx| size(gpx).saturating_sub(empty))
+        .max()
+        .unwrap_or_default();
+    size(around) + header + SLACK
+}
+
+// Returns the points written into the file of a chunk, these are the carried points and the points of the chunk,
+// where its first point is skipped, if it is not shared.
+fn written(carried: &[Waypoint], shared: bool, chunk: &[Waypoint]) -> Vec<Waypoint> {
+    carried
+        .iter()
+        .chain(chunk.iter().skip(usize::from(!shared)))
+        .cloned()
+        .collect()
+}
+
+// divide the points at the split, the points for the next chunk are kept and the points of the current chunk are returned
+// together with true, if the next chunk starts at the point of the split
+fn divide(points: &mut Vec<Waypoint>, split: &Split) -> (Vec<Waypoint>, bool) {
+    let (next, connected) = match split {
+        //the point at the split is the first point of the next chunk
+        Split::At(index) => {
+            let next = points.split_off(index + 1);
+            (
+                iter::once(points[*index].clone()).chain(next).collect(),
+                true,
+            )
+        }
+        Split::Before(index) => (points.split_off(*index), false),
+        Split::Skip(end, start) => {
+            let next = points.split_off(*start);
+            points.truncate(*end);
+            (next, false)
+        }
+    };
+    (mem::replace(points, next), connected)
 }
 
 //--------------------------------------------------------------
@@ -239,7 +482,10 @@ fn clear_points(points: &[Waypoint]) -> Vec<Waypoint> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::geo::distance_all;
     use crate::limit::Limit;
+    use approx_eq::assert_approx_eq;
+    use geo_types::Point;
     use gpx::{Route, Track, TrackSegment, Waypoint};
 
     #[test]
@@ -273,6 +519,64 @@ mod tests {
         assert_points(first_points, middle_points, last_points);
     }
 
+    #[test]
+    fn split_route_long_segment() {
+        let mut route = Route::new();
+        route.points.push(Waypoint::new(Point::new(0.0, 0.0)));
+        //roughly 1113 m
+        route.points.push(Waypoint::new(Point::new(0.01, 0.0)));
+
+        let lim = Limit::strict_length(300);
+        let routes = RouteSplitter::new(lim).split(&[route]);
+
+        assert_eq!(4, routes.len());
+        routes.iter().take(3).for_each(|r| {
+            assert_eq!(2, r.points.len());
+            assert_approx_eq!(300.0, distance_all(&r.points), 1e-6);
+        });
+    }
+
+    #[test]
+    fn split_route_overlap() {
+        let route = new_route(6);
+
+        let mut splitter = RouteSplitter::new(Limit::points(3)).with_overlap(Overlap::Points(0));
+        let routes = splitter.split(std::slice::from_ref(&route));
+        let points: Vec<Vec<String>> = routes.iter().map(|r| names_of(&r.points)).collect();
+        assert_eq!(
+            vec![
+                names(&["point 0", "point 1", "point 2"]),
+                names(&["point 3", "point 4", "point 5"])
+            ],
+            points
+        );
+
+        let mut splitter = RouteSplitter::new(Limit::points(4)).with_overlap(Overlap::Points(2));
+        let routes = splitter.split(std::slice::from_ref(&route));
+        let points: Vec<Vec<String>> = routes.iter().map(|r| names_of(&r.points)).collect();
+        assert_eq!(
+            vec![
+                names(&["point 0", "point 1", "point 2", "point 3"]),
+                names(&["point 2", "point 3", "point 4", "point 5"])
+            ],
+            points
+        );
+
+        //the overlap is capped, so that each chunk moves on
+        let mut splitter = RouteSplitter::new(Limit::points(3)).with_overlap(Overlap::Points(9));
+        let routes = splitter.split(&[route]);
+        let points: Vec<Vec<String>> = routes.iter().map(|r| names_of(&r.points)).collect();
+        assert_eq!(
+            vec![
+                names(&["point 0", "point 1", "point 2"]),
+                names(&["point 1", "point 2", "point 3"]),
+                names(&["point 2", "point 3", "point 4"]),
+                names(&["point 3", "point 4", "point 5"])
+            ],
+            points
+        );
+    }
+
     fn new_route(num_points: u32) -> Route {
         let mut route = Route::new();
         for i in 0..num_points {
@@ -352,6 +656,103 @@ mod tests {
         Box::new(TrackSplitter::new(lim))
     }
 
+    #[test]
+    fn divide_at() {
+        let mut points = new_points(4);
+
+        let (chunk, connected) = divide(&mut points, &Split::At(2));
+
+        assert_eq!(names(&["point 0", "point 1", "point 2"]), names_of(&chunk));
+        assert_eq!(names(&["point 2", "point 3"]), names_of(&points));
+        assert!(connected);
+    }
+
+    #[test]
+    fn written_with_overlap() {
+        let points = new_points(5);
+        //the first point is not written, when it is the last point of the previous chunk
+        assert_eq!(
+            names(&["point 3", "point 4"]),
+            names_of(&written(&[], false, &points[2..]))
+        );
+        assert_eq!(
+            names(&["point 0", "point 1", "point 2", "point 3", "point 4"]),
+            names_of(&written(&points[..2], true, &points[2..]))
+        );
+    }
+
+    #[test]
+    fn carry_points() {
+        let points = new_points(5);
+        assert_eq!(0, Overlap::Points(0).carry(&points));
+        assert_eq!(1, Overlap::Points(1).carry(&points));
+        //at most half of the chunk is carried over
+        assert_eq!(3, Overlap::Points(5).carry(&points));
+        assert_eq!(1, Overlap::Points(5).carry(&points[..2]));
+    }
+
+    #[test]
+    fn carry_meters() {
+        //roughly 111 m between the points
+        let points: Vec<Waypoint> = (0..5)
+            .map(|i| Waypoint::new(Point::new(f64::from(i) * 0.001, 0.0)))
+            .collect();
+        assert_eq!(1, Overlap::Meters(0).carry(&points));
+        assert_eq!(1, Overlap::Meters(100).carry(&points));
+        assert_eq!(3, Overlap::Meters(300).carry(&points));
+        assert_eq!(3, Overlap::Meters(1000).carry(&points));
+        assert_eq!(1, Overlap::Meters(1000).carry(&points[..1]));
+    }
+
+    #[test]
+    fn parse_overlap() {
+        assert_eq!(Ok(Overlap::Points(0)), "0".parse());
+        assert_eq!(Ok(Overlap::Points(3)), "3".parse());
+        assert_eq!(Ok(Overlap::Meters(300)), "300m".parse());
+        assert!("-1".parse::<Overlap>().is_err());
+        assert!("300 km".parse::<Overlap>().is_err());
+    }
+
+    #[test]
+    fn divide_skip() {
+        let mut points = new_points(5);
+
+        let (chunk, connected) = divide(&mut points, &Split::Skip(1, 3));
+        assert!(!connected);
+
+        assert_eq!(names(&["point 0"]), names_of(&chunk));
+        assert_eq!(names(&["point 3", "point 4"]), names_of(&points));
+    }
+
+    #[test]
+    fn divide_before() {
+        let mut points = new_points(4);
+
+        let (chunk, connected) = divide(&mut points, &Split::Before(3));
+        assert!(!connected);
+
+        assert_eq!(names(&["point 0", "point 1", "point 2"]), names_of(&chunk));
+        assert_eq!(names(&["point 3"]), names_of(&points));
+    }
+
+    fn new_points(num_points: u32) -> Vec<Waypoint> {
+        (0..num_points)
+            .map(|i| {
+                let mut point = Waypoint::default();
+                point.name = Some(format!("point {i}"));
+                point
+            })
+            .collect()
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(ToString::to_string).collect()
+    }
+
+    fn names_of(points: &[Waypoint]) -> Vec<String> {
+        points.iter().filter_map(|p| p.name.clone()).collect()
+    }
+
     fn assert_points(
         first_points: Vec<Waypoint>,
         middle_points: Vec<Waypoint>,
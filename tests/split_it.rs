@@ -1,6 +1,17 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::Duration;
+use time::macros::datetime;
+use time::OffsetDateTime;
+
+use approx_eq::assert_approx_eq;
+use gpx::{Track, Waypoint};
 use gpx_split::io::read_gpx;
-use gpx_split::limit::Limit;
-use gpx_split::split::{Context, RouteSplitter, TrackSplitter};
+use gpx_split::limit::{Filter, Limit, Measure};
+use gpx_split::loc::distance_all;
+use gpx_split::pace::Pace;
+use gpx_split::split::{Context, Overlap, RouteSplitter, TrackSplitter};
 
 #[test]
 fn track_length_too_long() {
@@ -37,11 +48,49 @@ fn track_points() {
     verify_track("target/debug/track_points", 2, 50);
 }
 
+#[test]
+fn track_overlap() {
+    let path = "target/debug/track_len.gpx".to_string();
+    let output = Some("target/debug/track_overlap.gpx".to_string());
+    let splitter =
+        Box::new(TrackSplitter::new(Limit::length(800)).with_overlap(Overlap::Meters(100)));
+
+    let mut ctx = Context::new(path, output, splitter);
+    let res = ctx.run().unwrap();
+
+    assert_eq!(3, res);
+    let points: Vec<Vec<Waypoint>> = (0..res)
+        .map(|i| read_gpx(&format!("target/debug/track_overlap_{i}.gpx")).unwrap())
+        .map(|gpx| gpx.tracks[0].segments[0].points.clone())
+        .collect();
+    for pair in points.windows(2) {
+        //the next track starts with the last points of the previous one within 100 m
+        let shared = pair[1]
+            .iter()
+            .take_while(|p| pair[0].iter().any(|q| q.point() == p.point()))
+            .count();
+        assert!(shared >= 1);
+        assert_eq!(
+            pair[0][pair[0].len() - shared..]
+                .iter()
+                .map(Waypoint::point)
+                .collect::<Vec<_>>(),
+            pair[1][..shared]
+                .iter()
+                .map(Waypoint::point)
+                .collect::<Vec<_>>()
+        );
+        assert!(distance_all(&pair[1][..shared]) <= 100.0);
+    }
+}
+
 #[test]
 fn track_location() {
     let path = "target/debug/track_loc.gpx".to_string();
     let waypoints = "target/debug/pois.gpx";
-    let splitter = Box::new(TrackSplitter::new(Limit::location(waypoints, 39)));
+    let splitter = Box::new(TrackSplitter::new(
+        Limit::location(waypoints, 39, &Filter::default()).unwrap(),
+    ));
 
     let mut ctx = Context::new(path, None, splitter);
     let res = ctx.run().unwrap();
@@ -50,6 +99,463 @@ fn track_location() {
     verify_track("target/debug/track_loc", 3, 280);
 }
 
+#[test]
+fn track_location_route() {
+    let path = "target/debug/track_loc.gpx".to_string();
+    let output = Some("target/debug/track_loc_route.gpx".to_string());
+    //the same locations as points of a route
+    let waypoints = "target/debug/pois_route.gpx";
+    let splitter = Box::new(TrackSplitter::new(
+        Limit::location(waypoints, 39, &Filter::default()).unwrap(),
+    ));
+
+    let mut ctx = Context::new(path, output, splitter);
+    let res = ctx.run().unwrap();
+
+    assert_eq!(3, res);
+    verify_track("target/debug/track_loc_route", 3, 280);
+}
+
+#[test]
+fn track_duration() {
+    let path = "target/debug/track_time.gpx".to_string();
+    let splitter = Box::new(TrackSplitter::new(Limit::duration(Duration::from_secs(
+        30 * 60,
+    ))));
+
+    let mut ctx = Context::new(path, None, splitter);
+    let res = ctx.run().unwrap();
+
+    assert_eq!(3, res);
+    verify_track("target/debug/track_time", 3, 6);
+}
+
+#[test]
+fn track_gap() {
+    let path = "target/debug/track_gap.gpx".to_string();
+    let splitter = Box::new(TrackSplitter::new(Limit::gap(Duration::from_secs(
+        2 * 60 * 60,
+    ))));
+
+    let mut ctx = Context::new(path, None, splitter);
+    let res = ctx.run().unwrap();
+
+    assert_eq!(3, res);
+    let expected = [
+        (
+            20,
+            datetime!(2021-07-15 11:00:16 UTC),
+            datetime!(2021-07-15 11:23:53 UTC),
+        ),
+        (
+            20,
+            datetime!(2021-07-15 14:24:43 UTC),
+            datetime!(2021-07-15 14:48:45 UTC),
+        ),
+        (
+            16,
+            datetime!(2021-07-16 11:48:47 UTC),
+            datetime!(2021-07-16 12:07:46 UTC),
+        ),
+    ];
+    for (i, (len, start, end)) in expected.iter().enumerate() {
+        let gpx = read_gpx(&format!("target/debug/track_gap_{i}.gpx")).unwrap();
+        let points = &gpx.tracks[0].segments[0].points;
+        assert_eq!(*len, points.len());
+        assert_eq!(*start, OffsetDateTime::from(points[0].time.unwrap()));
+        assert_eq!(*end, OffsetDateTime::from(points[len - 1].time.unwrap()));
+    }
+}
+
+#[test]
+fn track_elevation() {
+    let path = "target/debug/track_ele.gpx".to_string();
+    let splitter = Box::new(TrackSplitter::new(Limit::elevation(60, None, 2)));
+
+    let mut ctx = Context::new(path, None, splitter);
+    let res = ctx.run().unwrap();
+
+    assert_eq!(3, res);
+    verify_track("target/debug/track_ele", 3, 6);
+}
+
+#[test]
+fn track_any() {
+    let path = "target/debug/track_len.gpx".to_string();
+    let output = Some("target/debug/track_any.gpx".to_string());
+    let limit = Limit::any(vec![Limit::length(5000), Limit::points(20)]);
+    let splitter = Box::new(TrackSplitter::new(limit));
+
+    let mut ctx = Context::new(path, output, splitter);
+    let res = ctx.run().unwrap();
+
+    assert_eq!(3, res);
+    verify_track("target/debug/track_any", 3, 20);
+}
+
+#[test]
+fn track_all() {
+    let path = "target/debug/track_len.gpx".to_string();
+    let output = Some("target/debug/track_all.gpx".to_string());
+    let limit = Limit::all(vec![Limit::length(800), Limit::points(30)]);
+    let splitter = Box::new(TrackSplitter::new(limit));
+
+    let mut ctx = Context::new(path, output, splitter);
+    let res = ctx.run().unwrap();
+
+    assert_eq!(2, res);
+    verify_track("target/debug/track_all", 2, 30);
+}
+
+#[test]
+fn track_day() {
+    let path = "target/debug/track_gap.gpx".to_string();
+    let output = Some("target/debug/track_day.gpx".to_string());
+    let splitter = Box::new(TrackSplitter::new(Limit::day("-12:00".parse().unwrap())));
+
+    let mut ctx = Context::new(path, output, splitter);
+    let res = ctx.run().unwrap();
+
+    assert_eq!(3, res);
+    for (date, len) in [("2021-07-14", 20), ("2021-07-15", 29), ("2021-07-16", 7)] {
+        let gpx = read_gpx(&format!("target/debug/track_day_{date}.gpx")).unwrap();
+        assert_eq!(len, gpx.tracks[0].segments[0].points.len());
+    }
+}
+
+#[test]
+fn track_loops() {
+    let path = "target/debug/track_loops.gpx".to_string();
+    let splitter = Box::new(TrackSplitter::new(Limit::loops(30)));
+
+    let mut ctx = Context::new(path, None, splitter);
+    let res = ctx.run().unwrap();
+
+    //each loop starts and ends at the same point
+    assert_eq!(3, res);
+    for i in 0..3 {
+        let gpx = read_gpx(&format!("target/debug/track_loops_{i}.gpx")).unwrap();
+        let points = &gpx.tracks[0].segments[0].points;
+        assert_eq!(44, points.len());
+        assert_eq!(
+            points.first().unwrap().point(),
+            points.last().unwrap().point()
+        );
+    }
+}
+
+#[test]
+fn track_loops_overlap() {
+    //without overlap or with a long one, each loop still starts at the split
+    for (overlap, name) in [
+        (Overlap::Points(0), "track_loops_disjoint"),
+        (Overlap::Meters(5000), "track_loops_far"),
+    ] {
+        let path = "target/debug/track_loops.gpx".to_string();
+        let output = Some(format!("target/debug/{name}.gpx"));
+        let splitter = Box::new(TrackSplitter::new(Limit::loops(30)).with_overlap(overlap));
+
+        let mut ctx = Context::new(path, output, splitter);
+        let res = ctx.run().unwrap();
+
+        assert_eq!(3, res);
+    }
+    //the point at the split ends the previous loop and is not written again
+    let first = read_gpx("target/debug/track_loops_disjoint_0.gpx").unwrap();
+    let second = read_gpx("target/debug/track_loops_disjoint_1.gpx").unwrap();
+    assert_ne!(
+        first.tracks[0].segments[0].points.last().unwrap().point(),
+        second.tracks[0].segments[0].points[0].point()
+    );
+}
+
+#[test]
+fn track_points_overlap_too_large() {
+    let path = "target/debug/track_len.gpx".to_string();
+    let output = Some("target/debug/track_points_overlap.gpx".to_string());
+    let splitter =
+        Box::new(TrackSplitter::new(Limit::points(10)).with_overlap(Overlap::Points(20)));
+
+    let mut ctx = Context::new(path, output, splitter);
+    let res = ctx.run().unwrap();
+
+    //at most half of each file is carried over, so each file has at least five new points
+    let points: Vec<usize> = (0..res)
+        .map(|i| read_gpx(&format!("target/debug/track_points_overlap_{i}.gpx")).unwrap())
+        .map(|gpx| gpx.tracks[0].segments[0].points.len())
+        .collect();
+    assert!(points.iter().all(|&len| len <= 10));
+    assert!(res <= 56 / 5);
+}
+
+#[test]
+fn track_gates() {
+    let path = "target/debug/track_loops.gpx".to_string();
+    let output = Some("target/debug/track_gates.gpx".to_string());
+    let splitter = Box::new(TrackSplitter::new(
+        Limit::gates("target/debug/gates.gpx").unwrap(),
+    ));
+
+    let mut ctx = Context::new(path, output, splitter);
+    let res = ctx.run().unwrap();
+
+    //both gates are crossed twice
+    assert_eq!(5, res);
+    let sizes: Vec<usize> = (0..5)
+        .map(|i| {
+            let gpx = read_gpx(&format!("target/debug/track_gates_{i}.gpx")).unwrap();
+            gpx.tracks[0].segments[0].points.len()
+        })
+        .collect();
+    assert_eq!(vec![10, 28, 19, 28, 53], sizes);
+    let gpx = read_gpx("target/debug/track_gates_1.gpx").unwrap();
+    let first = &gpx.tracks[0].segments[0].points[0];
+    assert_eq!(Some("Checkpoint 1".to_string()), first.name);
+}
+
+#[test]
+fn track_fences() {
+    let path = "target/debug/track_loops.gpx".to_string();
+    let output = Some("target/debug/track_fences.gpx".to_string());
+    let splitter = Box::new(TrackSplitter::new(
+        Limit::fences("target/debug/areas.gpx").unwrap(),
+    ));
+
+    let mut ctx = Context::new(path, output, splitter);
+    let res = ctx.run().unwrap();
+
+    //entering and leaving the area
+    assert_eq!(3, res);
+    let tracks: Vec<Track> = (0..3)
+        .map(|i| {
+            let gpx = read_gpx(&format!("target/debug/track_fences_{i}.gpx")).unwrap();
+            gpx.tracks[0].clone()
+        })
+        .collect();
+    let sizes: Vec<usize> = tracks.iter().map(|t| t.segments[0].points.len()).collect();
+    assert_eq!(vec![10, 28, 96], sizes);
+    let tags: Vec<Option<String>> = tracks.iter().map(|t| t.type_.clone()).collect();
+    assert_eq!(vec![None, Some("Summit".to_string()), None], tags);
+}
+
+#[test]
+fn track_fences_overlap() {
+    let path = "target/debug/track_loops.gpx".to_string();
+    let output = Some("target/debug/track_fences_overlap.gpx".to_string());
+    let splitter = Box::new(
+        TrackSplitter::new(Limit::fences("target/debug/areas.gpx").unwrap())
+            .with_overlap(Overlap::Points(3)),
+    );
+
+    let mut ctx = Context::new(path, output, splitter);
+    let res = ctx.run().unwrap();
+
+    //the carried points do not change the splits or the tags
+    assert_eq!(3, res);
+    let tracks: Vec<Track> = (0..3)
+        .map(|i| {
+            let gpx = read_gpx(&format!("target/debug/track_fences_overlap_{i}.gpx")).unwrap();
+            gpx.tracks[0].clone()
+        })
+        .collect();
+    let sizes: Vec<usize> = tracks.iter().map(|t| t.segments[0].points.len()).collect();
+    assert_eq!(vec![10, 30, 98], sizes);
+    let tags: Vec<Option<String>> = tracks.iter().map(|t| t.type_.clone()).collect();
+    assert_eq!(vec![None, Some("Summit".to_string()), None], tags);
+}
+
+#[test]
+fn track_stage() {
+    let path = "target/debug/track_loc.gpx".to_string();
+    let output = Some("target/debug/track_stage.gpx".to_string());
+    let pois = read_gpx("target/debug/pois.gpx").unwrap().waypoints;
+    let splitter = Box::new(TrackSplitter::new(
+        Limit::stages(vec![pois], 10000..=30000, 1000, &Filter::default()).unwrap(),
+    ));
+
+    let mut ctx = Context::new(path, output, splitter);
+    let res = ctx.run().unwrap();
+
+    //both stages end at a POI, which is the first point of the next stage too
+    assert_eq!(3, res);
+    let points: Vec<_> = (0..3)
+        .map(|i| {
+            let gpx = read_gpx(&format!("target/debug/track_stage_{i}.gpx")).unwrap();
+            gpx.tracks[0].segments[0].points.clone()
+        })
+        .collect();
+    let sizes: Vec<usize> = points.iter().map(Vec::len).collect();
+    assert_eq!(vec![284, 346, 359], sizes);
+    let ends: Vec<String> = points[..2]
+        .iter()
+        .filter_map(|points| points.last()?.name.clone())
+        .collect();
+    assert_eq!(vec!["nearby Stop1", "nearby Stop2"], ends);
+}
+
+#[test]
+fn track_effort() {
+    let path = "target/debug/track_loc.gpx".to_string();
+    let output = Some("target/debug/track_effort.gpx".to_string());
+    let pois = read_gpx("target/debug/pois.gpx").unwrap().waypoints;
+    let splitter = Box::new(TrackSplitter::new(
+        Limit::optimal(3, Some((vec![pois], 100)), &Filter::default()).unwrap(),
+    ));
+
+    let mut ctx = Context::new(path, output, splitter);
+    let res = ctx.run().unwrap();
+
+    //the cuts can only be near the two POIs
+    assert_eq!(3, res);
+    let sizes: Vec<usize> = (0..3)
+        .map(|i| {
+            let gpx = read_gpx(&format!("target/debug/track_effort_{i}.gpx")).unwrap();
+            gpx.tracks[0].segments[0].points.len()
+        })
+        .collect();
+    assert_eq!(vec![283, 344, 360], sizes);
+}
+
+#[test]
+fn track_size() {
+    let path = "target/debug/track_loc.gpx".to_string();
+    let output = Some("target/debug/track_size.gpx".to_string());
+    let splitter = Box::new(TrackSplitter::new(Limit::size(40000)));
+
+    let mut ctx = Context::new(path, output, splitter);
+    let res = ctx.run().unwrap();
+
+    //the source has about 139 KB
+    assert_eq!(4, res);
+    for i in 0..4 {
+        let size = fs::metadata(format!("target/debug/track_size_{i}.gpx"))
+            .unwrap()
+            .len();
+        assert!(size <= 40000);
+        assert!(i == 3 || size > 39000);
+    }
+}
+
+#[test]
+fn route_size_too_small() {
+    let path = "target/debug/route_len.gpx".to_string();
+    let output = Some("target/debug/route_size.gpx".to_string());
+    let _ = fs::remove_file("target/debug/route_size_0.gpx");
+    //the metadata alone is above the budget
+    let splitter = Box::new(RouteSplitter::new(Limit::size(500)));
+
+    let mut ctx = Context::new(path, output, splitter);
+    let err = ctx.run().unwrap_err();
+
+    assert_eq!(ErrorKind::FileTooLarge, err.kind());
+    //the file above the budget is not written
+    assert!(!Path::new("target/debug/route_size_0.gpx").exists());
+}
+
+#[test]
+fn track_mode() {
+    let path = "target/debug/track_mode.gpx".to_string();
+    let bands = ["walk:8", "bike:40", "motor"]
+        .iter()
+        .map(|band| band.parse().unwrap())
+        .collect();
+    let splitter = Box::new(TrackSplitter::new(Limit::mode(
+        bands,
+        Duration::from_secs(60),
+    )));
+
+    let mut ctx = Context::new(path, None, splitter);
+    let res = ctx.run().unwrap();
+
+    //walking, cycling and walking again
+    assert_eq!(3, res);
+    let tracks: Vec<Track> = (0..3)
+        .map(|i| {
+            let gpx = read_gpx(&format!("target/debug/track_mode_{i}.gpx")).unwrap();
+            gpx.tracks[0].clone()
+        })
+        .collect();
+    let sizes: Vec<usize> = tracks.iter().map(|t| t.segments[0].points.len()).collect();
+    assert_eq!(vec![57, 124, 32], sizes);
+    let modes: Vec<String> = tracks.iter().filter_map(|t| t.type_.clone()).collect();
+    assert_eq!(vec!["walk", "bike", "walk"], modes);
+}
+
+#[test]
+fn track_mode_overlap() {
+    let path = "target/debug/track_mode.gpx".to_string();
+    let output = Some("target/debug/track_mode_overlap.gpx".to_string());
+    let bands = ["walk:8", "bike:40", "motor"]
+        .iter()
+        .map(|band| band.parse().unwrap())
+        .collect();
+    let splitter = Box::new(
+        TrackSplitter::new(Limit::mode(bands, Duration::from_secs(60)))
+            .with_overlap(Overlap::Points(3)),
+    );
+
+    let mut ctx = Context::new(path, output, splitter);
+    let res = ctx.run().unwrap();
+
+    //the carried points do not change the splits or the modes
+    assert_eq!(3, res);
+    let tracks: Vec<Track> = (0..3)
+        .map(|i| {
+            let gpx = read_gpx(&format!("target/debug/track_mode_overlap_{i}.gpx")).unwrap();
+            gpx.tracks[0].clone()
+        })
+        .collect();
+    let sizes: Vec<usize> = tracks.iter().map(|t| t.segments[0].points.len()).collect();
+    assert_eq!(vec![57, 126, 34], sizes);
+    let modes: Vec<String> = tracks.iter().filter_map(|t| t.type_.clone()).collect();
+    assert_eq!(vec!["walk", "bike", "walk"], modes);
+}
+
+#[test]
+fn track_dwell() {
+    let path = "target/debug/track_dwell.gpx".to_string();
+    let splitter = Box::new(TrackSplitter::new(Limit::dwell(
+        20,
+        Duration::from_secs(30 * 60),
+    )));
+
+    let mut ctx = Context::new(path, None, splitter);
+    let res = ctx.run().unwrap();
+
+    assert_eq!(2, res);
+    let first = read_gpx("target/debug/track_dwell_0.gpx").unwrap();
+    let first = &first.tracks[0].segments[0].points;
+    let second = read_gpx("target/debug/track_dwell_1.gpx").unwrap();
+    let second = &second.tracks[0].segments[0].points;
+    assert_eq!(27, first.len());
+    assert_eq!(28, second.len());
+    //the points of the stop are neither at the end of the first nor at the start of the second track
+    let end = first.last().and_then(|p| p.time).unwrap();
+    assert_eq!(
+        datetime!(2021-07-15 11:29:54 UTC),
+        OffsetDateTime::from(end)
+    );
+    let start = second.first().and_then(|p| p.time).unwrap();
+    assert_eq!(
+        datetime!(2021-07-15 12:18:43 UTC),
+        OffsetDateTime::from(start)
+    );
+}
+
+#[test]
+fn track_parts() {
+    let path = "target/debug/track_len.gpx".to_string();
+    let output = Some("target/debug/track_parts.gpx".to_string());
+    let splitter = Box::new(TrackSplitter::new(Limit::parts(Measure::Points, 5)));
+
+    let mut ctx = Context::new(path, output, splitter);
+    let res = ctx.run().unwrap();
+
+    //55 segments in 5 parts with 11 segments each
+    assert_eq!(5, res);
+    verify_track("target/debug/track_parts", 5, 12);
+}
+
 fn verify_track(pattern: &str, files: usize, min_points: usize) {
     for i in 0..files {
         let p = pattern.to_owned() + &format!("_{i}.gpx");
@@ -65,6 +571,23 @@ fn verify_track(pattern: &str, files: usize, min_points: usize) {
     }
 }
 
+#[test]
+fn route_pace() {
+    let path = "target/debug/route_len.gpx".to_string();
+    let output = Some("target/debug/route_pace.gpx".to_string());
+    //the route has no elevation, so Naismith's rule is 5 km/h like the length of 5 km in an hour
+    let splitter = Box::new(RouteSplitter::new(Limit::pace(
+        Pace::Naismith,
+        Duration::from_secs(3600),
+    )));
+
+    let mut ctx = Context::new(path, output, splitter);
+    let res = ctx.run().unwrap();
+
+    assert_eq!(3, res);
+    verify_route("target/debug/route_pace", 3, 18);
+}
+
 #[test]
 fn route_length() {
     let path = "target/debug/route_len.gpx".to_string();
@@ -89,6 +612,23 @@ fn route_points() {
     verify_route("target/debug/route_points", 2, 40);
 }
 
+#[test]
+fn route_strict_length() {
+    let path = "target/debug/route_len.gpx".to_string();
+    let output = Some("target/debug/route_strict.gpx".to_string());
+    let splitter = Box::new(RouteSplitter::new(Limit::strict_length(5000)));
+
+    let mut ctx = Context::new(path, output, splitter);
+    let res = ctx.run().unwrap();
+
+    assert_eq!(3, res);
+    for i in 0..res - 1 {
+        let gpx = read_gpx(&format!("target/debug/route_strict_{i}.gpx")).unwrap();
+        let length = distance_all(&gpx.routes[0].points);
+        assert_approx_eq!(5000.0, length, 1e-6);
+    }
+}
+
 fn verify_route(pattern: &str, files: usize, min_points: usize) {
     for i in 0..files {
         let p = pattern.to_owned() + &format!("_{i}.gpx");